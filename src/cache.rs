@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git::Git;
+use crate::helpers::file;
+use crate::result::{Context, Result};
+
+/// Directory name of the bare repository mirrors inside the cache directory.
+const MIRRORS_DIR_NAME: &str = "mirrors";
+
+/// A cached bare repository mirror.
+pub struct CacheEntry {
+    /// Mirror directory.
+    pub path: PathBuf,
+    /// Remote repository URL of the mirror (if readable).
+    pub url: Option<String>,
+    /// Mirror size in bytes.
+    pub size: u64,
+}
+
+/// Cache of bare Git repository mirrors keyed by remote repository URL.
+/// Package checkouts are materialized from them so the network is only used to fetch new refs.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Create a new cache placed on a given directory.
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join(MIRRORS_DIR_NAME),
+        }
+    }
+
+    /// Return the mirror directory of a remote repository URL.
+    /// E.g `~/.cache/paket/mirrors/gitnow-3f2a9c1e0b7d4a65.git`
+    pub fn mirror_dir(&self, url: &str) -> PathBuf {
+        let url = url.trim().trim_end_matches('/');
+        let name = url
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".git")
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || ['-', '_', '.'].contains(c))
+            .collect::<String>();
+        let hash = file::checksum_bytes(url.as_bytes());
+
+        self.dir.join(format!("{}-{}.git", name, &hash[..16]))
+    }
+
+    /// Return all cached mirrors along with their remote repository URLs and sizes.
+    pub fn entries(&self, git: &Git) -> Result<Vec<CacheEntry>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir).with_context(|| {
+            format!(
+                "cache directory \"{}\" can not be read.",
+                self.dir.display()
+            )
+        })? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            entries.push(CacheEntry {
+                url: git.remote_url(&path).ok(),
+                size: file::dir_size(&path)?,
+                path,
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(entries)
+    }
+
+    /// Remove a cached mirror.
+    pub fn remove(&self, entry: &CacheEntry) -> Result {
+        fs::remove_dir_all(&entry.path).with_context(|| {
+            format!(
+                "cached mirror \"{}\" can not be removed.",
+                entry.path.display()
+            )
+        })
+    }
+}
@@ -1,214 +1,1618 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::git::Git;
-use crate::paket::{Paket, PaketEvents};
-use crate::pkg::fmt::PkgNameFmt;
+use crate::cache::Cache;
+use crate::cli::OnModified;
+use crate::git::{self, Git, RefKind};
+use crate::helpers::{file as helper_file, pool, table};
+use crate::paket::{Paket, PaketEvents, PkgFileKind};
+use crate::pkg::config::{TomlManifest, TomlPackage};
+use crate::pkg::fmt::{self, PkgNameFmt};
+use crate::pkg::lock::{LockedPackage, Lockfile};
+use crate::pkg::packages::TomlPackages;
+use crate::pkg::receipt::{DisplacedFile, Receipt, ReceiptFile, ReceiptSource, Receipts};
 use crate::result::{Context, Result};
+use crate::transaction::{Transaction, JOURNAL_FILE_NAME};
+use crate::watch::Watcher;
+
+/// Delay without further changes to wait for before syncing a package being developed.
+const DEV_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Directory name inside the Paket directory where files replaced by packages are kept.
+const DISPLACED_DIR_NAME: &str = ".displaced";
+
+/// Suffix of the copies of locally modified files made before replacing or removing them.
+const MODIFIED_BACKUP_SUFFIX: &str = ".paket-backup";
+
+/// Installed package entry printed by the `list` command.
+#[derive(Serialize, Debug)]
+struct ListEntry<'r> {
+    id: &'r str,
+    name: &'r str,
+    version: String,
+    source: &'static str,
+    url: Option<&'r str>,
+    path: Option<&'r Path>,
+    reference: Option<&'r str>,
+    commit: Option<&'r str>,
+}
+
+/// Installed package entry printed by the `outdated` command.
+#[derive(Serialize, Debug)]
+struct OutdatedEntry<'r> {
+    id: &'r str,
+    current: String,
+    wanted: String,
+    latest: Option<String>,
+    outdated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Git origin of a remote package resolved at installation or update time.
+struct PkgOrigin {
+    /// Git host provider (if the package was not given as a Git URL).
+    provider: Option<String>,
+    /// Requested or resolved Git branch or tag.
+    reference: String,
+    /// Kind of the Git reference (if known).
+    ref_kind: Option<RefKind>,
+    /// Requested semver requirement the Git tag was resolved from (if any).
+    requirement: Option<String>,
+}
+
+/// Package directory prepared by the network-bound part of an installation or update.
+struct PkgCheckout {
+    /// Package directory.
+    pkg_dir: PathBuf,
+    /// Git origin of a remote package.
+    origin: Option<PkgOrigin>,
+    /// Whether the package is a local one.
+    is_pkg_local: bool,
+    /// Whether the package is already up to date so nothing was fetched.
+    up_to_date: bool,
+}
 
 /// Define actions for every `Paket` command.
 pub struct Actions<'a> {
     paket: &'a Paket,
     git: Git,
+    receipts: Receipts,
+    cache: Cache,
+    /// Package checkouts prepared in advance keyed by package identifier.
+    checkouts: BTreeMap<String, PkgCheckout>,
+    /// Changes of the running package transaction (if any).
+    tx: Option<Transaction>,
 }
 
 impl<'a> Actions<'a> {
     /// Create a new `Action` instance based on `Paket` object.
     pub fn new(paket: &'a Paket) -> Result<Self> {
-        let git = Git::new(paket.paths.paket_dir.clone())?;
-        Ok(Self { paket, git })
+        let git = Git::new(paket.paths.paket_dir.clone(), &paket.settings)?;
+
+        // Restore a consistent state after an operation which was interrupted
+        let journal = paket.paths.paket_dir.join(JOURNAL_FILE_NAME);
+        if journal.exists() {
+            println!("Recovering from an interrupted operation...");
+            if let Err(err) = Transaction::recover(&journal, &git) {
+                println!("Warning: changes could not be fully recovered: {:#}", err);
+            }
+        }
+
+        let receipts = Receipts::load(&paket.paths.paket_dir)?;
+        let cache = Cache::new(&paket.paths.cache_dir);
+        Ok(Self {
+            paket,
+            git,
+            receipts,
+            cache,
+            checkouts: BTreeMap::new(),
+            tx: None,
+        })
     }
 
-    /// Command action to install a new package and invoke a `paket_install` Fish shell event.
-    pub fn install(&mut self, pkg_name: &str, git_provider: &str) -> Result {
+    /// Record the installation receipts of the packages installed before receipts existed,
+    /// which are always remote packages, so they are handled like any other one.
+    /// It only runs before commands which change packages since it writes the receipts.
+    fn record_missing_receipts(&mut self) -> Result {
+        let base_dir = self.git.base_dir.clone();
+        if !base_dir.is_dir() {
+            return Ok(());
+        }
+
+        let mut pkg_names = vec![];
+        for user_entry in fs::read_dir(&base_dir)? {
+            let user_name = user_entry?.file_name().to_string_lossy().into_owned();
+            let user_dir = base_dir.join(&user_name);
+            if user_name.starts_with('.') || !user_dir.is_dir() {
+                continue;
+            }
+            for pkg_entry in fs::read_dir(&user_dir)? {
+                let pkg_name =
+                    [&user_name, "/", &pkg_entry?.file_name().to_string_lossy()].concat();
+                if base_dir.join(&pkg_name).join(".git").exists()
+                    && self.receipts.get(&pkg_name).is_none()
+                {
+                    pkg_names.push(pkg_name);
+                }
+            }
+        }
+        if pkg_names.is_empty() {
+            return Ok(());
+        }
+
+        let mut recorded = false;
+        for pkg_name in pkg_names {
+            match self.transaction(|a| a.record_missing_receipt(&pkg_name)) {
+                Ok(is_recorded) => recorded |= is_recorded,
+                Err(err) => eprintln!(
+                    "Warning: installation receipt of package `{}` could not be recorded: {:#}",
+                    pkg_name, err
+                ),
+            }
+        }
+        if recorded {
+            self.write_lockfile()?;
+        }
+        Ok(())
+    }
+
+    /// Record the installation receipt of a package installed without one returning `false`
+    /// if its repository is not a package installation (e.g a clone left by an interrupted
+    /// command) which happens when it has no checked out commit or some package file is missing.
+    /// The package files which no other package owns are its own.
+    fn record_missing_receipt(&mut self, pkg_name: &str) -> Result<bool> {
         let pkg_fmt = PkgNameFmt::from(pkg_name)?;
-        let pkg_name = &pkg_fmt.get_short_name();
-        let pkg_tag = pkg_fmt.pkg_tag.as_str().trim();
-        let pkg_tag = if pkg_tag.is_empty() {
-            None
-        } else {
-            Some(pkg_tag)
+        let pkg_id = pkg_fmt.get_id();
+        let pkg_dir = self.git.base_dir.join(pkg_name);
+        if self.git.head_commit(&pkg_dir).is_err() {
+            return Ok(false);
+        }
+        let manifest = self
+            .paket
+            .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, false)?;
+        let Some(toml_pkg) = manifest.package else {
+            bail!("`paket.toml` file could not be parsed correctly.")
         };
-        let branch_tag = pkg_tag.unwrap_or("");
-        let mut is_pkg_local = false;
 
-        // Check for a local package (directory path) or a remote one
-        let pkg_dir = if let Some(pkg_path) = pkg_fmt.get_pkg_path() {
-            is_pkg_local = true;
+        let pkg_files = self.paket.pkg_files(pkg_dir.clone(), &toml_pkg.include)?;
+        if pkg_files.is_empty() || pkg_files.iter().any(|f| !f.dest.exists()) {
+            return Ok(false);
+        }
 
-            // Check if package dir path is a valid Git repository
-            self.git
-                .check_valid_repo(&pkg_path)
-                .with_context(|| "provided package directory is not a valid Git repository.")?;
+        eprintln!("Recording installation receipt of package `{}`...", pkg_id);
+        let files = pkg_files
+            .into_iter()
+            .filter(|f| self.receipts.owner(&f.dest, &pkg_id).is_none())
+            .map(|f| {
+                Ok(ReceiptFile {
+                    hash: helper_file::checksum(&f.dest)?,
+                    path: f.dest,
+                    displaced: None,
+                    linked: false,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let dependencies = self.manifest_dependencies(&pkg_dir);
+        self.save_receipt(&pkg_fmt, &pkg_dir, &toml_pkg, None, dependencies, files)?;
 
-            println!(
-                "Installing package from directory `{}`...",
-                pkg_path.display()
+        Ok(true)
+    }
+
+    /// Run a package operation as a transaction restoring the previous state of the Fish shell
+    /// directories, package repositories and installation receipts if it fails.
+    /// Operations nested into a running transaction (e.g dependencies) are part of it.
+    fn transaction<T, F>(&mut self, op: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.tx.is_some() {
+            return op(self);
+        }
+
+        let receipts = self.receipts.clone();
+        self.tx = Some(Transaction::new(self.journal_path()));
+        let result = op(self);
+        let mut tx = self.tx.take().unwrap_or_default();
+
+        // Receipts are saved as part of the transaction so they always match the files
+        let result = result.and_then(|value| {
+            tx.keep_file(self.receipts.path())?;
+            self.receipts.save()?;
+            Ok(value)
+        });
+        match result {
+            Ok(value) => {
+                tx.commit()?;
+                Ok(value)
+            }
+            Err(err) => {
+                if !tx.is_empty() {
+                    println!("Rolling back the changes...");
+                }
+                if let Err(rollback_err) = tx.rollback(&self.git) {
+                    println!(
+                        "Warning: changes could not be fully rolled back: {:#}",
+                        rollback_err
+                    );
+                }
+                self.receipts = receipts;
+                Err(err)
+            }
+        }
+    }
+
+    /// Return the path of the journal file of the package transactions.
+    fn journal_path(&self) -> PathBuf {
+        self.paket.paths.paket_dir.join(JOURNAL_FILE_NAME)
+    }
+
+    /// Return the changes of the running package transaction.
+    fn tx(&mut self) -> &mut Transaction {
+        self.tx.get_or_insert_with(Transaction::default)
+    }
+
+    /// Revert the package checkouts prepared in advance which were not used removing
+    /// the new clones and restoring the updated repositories to their installed commits.
+    fn discard_checkouts(&mut self) -> Result {
+        let mut tx = Transaction::default();
+        for (pkg_id, checkout) in std::mem::take(&mut self.checkouts) {
+            if checkout.is_pkg_local || checkout.up_to_date {
+                continue;
+            }
+            match self.receipts.get(&pkg_id).and_then(|r| r.commit.as_ref()) {
+                Some(commit) => tx.checked_out(&pkg_id, commit)?,
+                None => tx.cloned(&checkout.pkg_dir)?,
+            }
+        }
+        tx.rollback(&self.git)
+    }
+
+    /// Copy all corresponding package files to Fish shell directories (or symlink them
+    /// if `link` is enabled) except the skipped ones returning the list of written files.
+    ///
+    /// Destinations provided by other packages or existing user files are conflicts which
+    /// fail unless `force` is enabled. Then the replaced files are kept in order to restore
+    /// them when the package no longer provides them.
+    fn copy_pkg_files(
+        &mut self,
+        pkg_id: &str,
+        pkg_dir: &Path,
+        pkg_include: &Option<Vec<String>>,
+        skipped: &[ReceiptFile],
+        force: bool,
+        link: bool,
+    ) -> Result<Vec<ReceiptFile>> {
+        let files = self
+            .paket
+            .pkg_files(pkg_dir.to_path_buf(), pkg_include)?
+            .into_iter()
+            .filter(|f| !skipped.iter().any(|s| s.path == f.dest))
+            .map(|f| (f.src, f.dest))
+            .collect::<Vec<_>>();
+
+        // Check for destinations owned by other packages or by the user
+        let own_files = self
+            .receipts
+            .get(pkg_id)
+            .map(|r| r.files.clone())
+            .unwrap_or_default();
+        let mut conflicts = vec![];
+        for (_, dest) in &files {
+            if let Some(owner) = self.receipts.owner(dest, pkg_id) {
+                conflicts.push((dest.clone(), Some(owner.to_owned())));
+            } else if dest.exists() && !own_files.iter().any(|f| &f.path == dest) {
+                conflicts.push((dest.clone(), None));
+            }
+        }
+        if !conflicts.is_empty() && !force {
+            let conflicts = conflicts
+                .iter()
+                .map(|(dest, owner)| match owner {
+                    Some(owner) => format!(
+                        "{} (provided by package `{}`)",
+                        self.fish_relative(dest).display(),
+                        owner
+                    ),
+                    None => format!("{} (existing file)", self.fish_relative(dest).display()),
+                })
+                .collect::<Vec<_>>();
+            bail!(
+                "package `{}` has conflicting files:\n  {}\nUse `--force` to replace them anyway.",
+                pkg_id,
+                conflicts.join("\n  ")
             );
-            pkg_path
+        }
+
+        let mut displaced = BTreeMap::new();
+        for (dest, owner) in conflicts {
+            let displaced_file = self.displace_file(pkg_id, &dest, owner)?;
+            displaced.insert(dest, displaced_file);
+        }
+        if link {
+            // Keep the links which already point to their package files
+            let unlinked = files
+                .iter()
+                .filter(|(src, dest)| fs::read_link(dest).ok().as_ref() != Some(src))
+                .cloned()
+                .collect::<Vec<_>>();
+            self.tx().link_files(&unlinked)?;
         } else {
-            println!("Installing package `{}@{}`...", &pkg_name, branch_tag);
+            self.tx().write_files(&files)?;
+        }
 
-            if self.paket.pkg_exists(pkg_name) {
+        files
+            .into_iter()
+            .map(|(_, dest)| {
+                // Keep track of the files replaced by previous versions of the package
+                let displaced = displaced.remove(&dest).or_else(|| {
+                    own_files
+                        .iter()
+                        .find(|f| f.path == dest)
+                        .and_then(|f| f.displaced.clone())
+                });
+                Ok(ReceiptFile {
+                    hash: helper_file::checksum(&dest)?,
+                    path: dest,
+                    displaced,
+                    linked: link,
+                })
+            })
+            .collect()
+    }
+
+    /// Keep a copy of a file which is going to be replaced by a package taking it over
+    /// from the package which provided it (if any).
+    fn displace_file(
+        &mut self,
+        pkg_id: &str,
+        path: &Path,
+        owner: Option<String>,
+    ) -> Result<DisplacedFile> {
+        let key = helper_file::checksum_bytes(format!("{}:{}", pkg_id, path.display()).as_bytes());
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let copy = self
+            .paket
+            .paths
+            .paket_dir
+            .join(DISPLACED_DIR_NAME)
+            .join(format!("{}-{}", &key[..16], file_name));
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match &owner {
+            Some(owner) => println!(
+                "Replacing file `{}` provided by package `{}`.",
+                path.display(),
+                owner
+            ),
+            None => println!("Replacing existing file `{}`.", path.display()),
+        }
+        self.tx().write_files(&[(path.to_owned(), copy.clone())])?;
+
+        // The replaced file is no longer provided by its package until it's restored
+        let previous = owner
+            .as_ref()
+            .and_then(|owner| self.receipts.take_file(owner, path))
+            .and_then(|f| f.displaced)
+            .map(Box::new);
+
+        Ok(DisplacedFile {
+            copy,
+            owner,
+            previous,
+        })
+    }
+
+    /// Restore a file replaced by a package which no longer provides it
+    /// giving it back to the package which provided it (if any).
+    fn restore_displaced_file(&mut self, path: &Path, displaced: DisplacedFile) -> Result {
+        if !displaced.copy.exists() {
+            println!(
+                "Warning: replaced file `{}` could not be restored since its copy is gone.",
+                path.display()
+            );
+            return self.tx().remove_file(path);
+        }
+
+        println!("Restoring replaced file `{}`.", path.display());
+        self.tx()
+            .write_files(&[(displaced.copy.clone(), path.to_owned())])?;
+        self.tx().remove_file(&displaced.copy)?;
+
+        if let Some(receipt) = displaced
+            .owner
+            .and_then(|owner| self.receipts.packages.get_mut(&owner))
+        {
+            receipt.files.push(ReceiptFile {
+                path: path.to_owned(),
+                hash: helper_file::checksum(path)?,
+                displaced: displaced.previous.map(|d| *d),
+                linked: false,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Remove a package file restoring the file it replaced (if any).
+    fn remove_pkg_file(&mut self, file: &ReceiptFile) -> Result {
+        match file.displaced.clone() {
+            Some(displaced) => self.restore_displaced_file(&file.path, displaced),
+            None => self.tx().remove_file(&file.path),
+        }
+    }
+
+    /// Forget the files of a package being removed which were replaced by other packages
+    /// so the files they replaced in turn are restored instead.
+    fn forget_displaced_files(&mut self, pkg_id: &str) -> Result {
+        let mut copies = vec![];
+        for receipt in self.receipts.packages.values_mut() {
+            for file in &mut receipt.files {
+                let is_owned = file
+                    .displaced
+                    .as_ref()
+                    .is_some_and(|d| d.owner.as_deref() == Some(pkg_id));
+                if is_owned {
+                    if let Some(displaced) = file.displaced.take() {
+                        copies.push(displaced.copy);
+                        file.displaced = displaced.previous.map(|d| *d);
+                    }
+                }
+            }
+        }
+        for copy in copies {
+            self.tx().remove_file(&copy)?;
+        }
+        Ok(())
+    }
+
+    /// Return a path relative to the Fish configuration directory (if possible).
+    fn fish_relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.paket.paths.fish_dir)
+            .unwrap_or(path)
+    }
+
+    /// Apply a policy to the installed files of a package which were modified locally
+    /// before replacing or removing them returning the files to leave untouched.
+    fn protect_modified_files(
+        &mut self,
+        pkg_id: &str,
+        files: &[ReceiptFile],
+        on_modified: OnModified,
+    ) -> Result<Vec<ReceiptFile>> {
+        let modified = files
+            .iter()
+            .filter(|f| !f.linked && f.path.exists())
+            .filter(|f| helper_file::checksum(&f.path).is_ok_and(|hash| hash != f.hash))
+            .cloned()
+            .collect::<Vec<_>>();
+        if modified.is_empty() {
+            return Ok(modified);
+        }
+
+        match on_modified {
+            OnModified::Abort => {
+                let paths = modified
+                    .iter()
+                    .map(|f| f.path.display().to_string())
+                    .collect::<Vec<_>>();
                 bail!(
-                    "package `{}` is already installed. Try to use the `up` command to upgrade it.",
-                    pkg_name
+                    "package `{}` has locally modified files:\n  {}\nUse `--on-modified skip` or `--on-modified backup` to continue.",
+                    pkg_id,
+                    paths.join("\n  ")
                 );
             }
+            OnModified::Skip => {
+                for file in &modified {
+                    println!("Skipping locally modified file `{}`.", file.path.display());
+                }
+                Ok(modified)
+            }
+            OnModified::Backup => {
+                for file in &modified {
+                    let mut backup = file.path.as_os_str().to_owned();
+                    backup.push(MODIFIED_BACKUP_SUFFIX);
+                    let backup = PathBuf::from(backup);
+                    println!(
+                        "Backing up locally modified file `{}` to `{}`.",
+                        file.path.display(),
+                        backup.display()
+                    );
+                    self.tx().write_files(&[(file.path.clone(), backup)])?;
+                }
+                Ok(vec![])
+            }
+        }
+    }
 
-            // Clone the remote repository
-            self.git.clone(pkg_name, pkg_tag, git_provider)?;
-
-            let pkg_dir = self.git.base_dir.join(pkg_name);
-            if !self.paket.pkg_exists(pkg_name) {
-                bail!("package `{}` was not cloned with success.", pkg_name);
+    /// Print the files added, removed or changed by a package update.
+    fn print_file_changes(&self, pkg_id: &str, previous: &[ReceiptFile], current: &[ReceiptFile]) {
+        let find = |files: &[ReceiptFile], path: &Path| {
+            files
+                .iter()
+                .find(|f| f.path == path)
+                .map(|f| f.hash.clone())
+        };
+        let mut changes = vec![];
+        for file in current {
+            match find(previous, &file.path) {
+                None => changes.push(('+', &file.path)),
+                Some(hash) if hash != file.hash => changes.push(('~', &file.path)),
+                Some(_) => {}
             }
-            pkg_dir
+        }
+        for file in previous {
+            if find(current, &file.path).is_none() {
+                changes.push(('-', &file.path));
+            }
+        }
+
+        let count = |kind| changes.iter().filter(|(k, _)| *k == kind).count();
+        println!(
+            "Files of package `{}`: {} added, {} removed, {} changed.",
+            pkg_id,
+            count('+'),
+            count('-'),
+            count('~')
+        );
+        for (kind, path) in changes {
+            println!("  {} {}", kind, self.fish_relative(path).display());
+        }
+    }
+
+    /// Record the installation receipt of a package into the receipts database
+    /// which is saved once the running transaction is committed.
+    fn save_receipt(
+        &mut self,
+        pkg_fmt: &PkgNameFmt,
+        pkg_dir: &Path,
+        toml_pkg: &TomlPackage,
+        origin: Option<PkgOrigin>,
+        dependencies: Vec<String>,
+        files: Vec<ReceiptFile>,
+    ) -> Result {
+        let source = match pkg_fmt.get_pkg_path() {
+            Some(path) => ReceiptSource::Local { path },
+            None => ReceiptSource::Remote {
+                url: self.git.remote_url(pkg_dir)?,
+            },
         };
 
-        // Process Fish shell package structure and read the Packet manifest
-        let manifest =
-            self.paket
-                .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, is_pkg_local)?;
+        let (provider, reference, ref_kind, requirement) = match origin {
+            Some(o) => (o.provider, Some(o.reference), o.ref_kind, o.requirement),
+            None => (None, None, None, None),
+        };
+        let receipt = Receipt {
+            name: toml_pkg.name.clone(),
+            version: toml_pkg.version.clone(),
+            source,
+            provider,
+            reference,
+            requirement,
+            ref_kind,
+            commit: self.git.head_commit(pkg_dir).ok(),
+            dependencies,
+            files,
+        };
 
-        if let Some(toml_pkg) = manifest.package {
-            // Copy all corresponding package files to Fish shell directories
-            self.paket
-                .scan_pkg_dir(pkg_dir, &toml_pkg.include, |src, dest| {
-                    fs::copy(src, dest)?;
-                    Ok(())
-                })?;
+        self.receipts.insert(&pkg_fmt.get_id(), receipt);
+        Ok(())
+    }
 
-            // Emit an `after_install` Fish shell event if there is an associated Paket event
-            if let Some(toml_events) = manifest.events {
-                self.paket
-                    .emit_event(&toml_pkg.name, &toml_events, PaketEvents::AfterInstall)?;
+    /// Resolve the Git branch or tag of a remote package which is either the requested one,
+    /// the configured default one or the default branch of the remote repository.
+    fn resolve_ref(&self, pkg_tag: &str, endpoint: &str) -> Result<String> {
+        let pkg_tag = pkg_tag.trim();
+        if !pkg_tag.is_empty() {
+            return Ok(pkg_tag.to_owned());
+        }
+        if let Some(default_ref) = &self.git.default_ref {
+            return Ok(default_ref.clone());
+        }
+
+        self.git
+            .remote_default_branch(endpoint)
+            .with_context(|| format!("default branch of `{}` could not be determined.", endpoint))
+    }
+
+    /// Resolve a version requirement (if any) to the highest matching Git tag of a repository
+    /// returning the Git branch or tag to use along with the requirement.
+    fn resolve_requirement(
+        &self,
+        endpoint: &str,
+        pkg_tag: String,
+    ) -> Result<(String, Option<String>)> {
+        match fmt::parse_version_req(&pkg_tag)? {
+            Some(req) => {
+                let tag = self.git.latest_matching_tag(endpoint, &req)?;
+                Ok((tag, Some(pkg_tag)))
             }
-        } else {
-            bail!("`paket.toml` file could not be parsed correctly.")
-        };
+            None => Ok((pkg_tag, None)),
+        }
+    }
 
-        println!("Package was installed successfully.");
-        println!("Now just reload your current Fish shell session.");
+    /// Return the kind of a Git reference on a repository.
+    /// References which are not a branch or a tag but look like a commit hash are commits.
+    fn resolve_ref_kind(&self, endpoint: &str, pkg_tag: &str) -> Result<RefKind> {
+        match self.git.remote_ref_kind(endpoint, pkg_tag) {
+            Ok(ref_kind) => Ok(ref_kind),
+            Err(_) if fmt::is_commit_sha(pkg_tag) => Ok(RefKind::Commit),
+            Err(err) => Err(err),
+        }
+    }
 
-        Ok(())
+    /// Create or refresh the cached mirror of a remote repository.
+    /// It fails in offline mode since it requires network access.
+    fn fetch_mirror(&self, mirror_dir: &Path, endpoint: &str) -> Result {
+        if self.paket.settings.offline() {
+            bail!(
+                "repository `{}` is not cached so it can not be fetched in offline mode.",
+                endpoint
+            );
+        }
+        self.git.mirror(mirror_dir, endpoint)
     }
 
-    /// Command action to update an existing package
-    pub fn update(&mut self, pkg_name: &str) -> Result {
-        let pkg_fmt = PkgNameFmt::from(pkg_name)?;
-        let pkg_name = &pkg_fmt.get_short_name();
-        let pkg_tag = pkg_fmt.pkg_tag.as_str().trim();
-        let pkg_tag = if pkg_tag.is_empty() {
-            None
-        } else {
-            Some(pkg_tag)
+    /// Resolve the requested Git reference of a remote package against its cached mirror
+    /// returning the package origin along with the commit to check out.
+    /// The Git reference of an exact commit could be gone so its kind is optional then.
+    fn resolve_mirror_ref(
+        &self,
+        mirror_dir: &Path,
+        pkg_tag: &str,
+        pkg_commit: Option<&str>,
+    ) -> Result<(PkgOrigin, String)> {
+        let mirror = mirror_dir.to_string_lossy();
+        let pkg_tag = self.resolve_ref(pkg_tag, &mirror)?;
+        let (pkg_tag, requirement) = self.resolve_requirement(&mirror, pkg_tag)?;
+        let ref_kind = match (pkg_commit, &requirement) {
+            (Some(_), _) => self.resolve_ref_kind(&mirror, &pkg_tag).ok(),
+            (None, Some(_)) => Some(RefKind::Tag),
+            (None, None) => Some(self.resolve_ref_kind(&mirror, &pkg_tag)?),
         };
-        let branch_tag = pkg_tag.unwrap_or("");
-        let mut is_pkg_local = false;
+        let commit = self
+            .git
+            .resolve_commit(mirror_dir, pkg_commit.unwrap_or(&pkg_tag))?;
 
-        // Check for a local package (directory path) or a remote one
-        let pkg_dir = if let Some(pkg_path) = pkg_fmt.get_pkg_path() {
-            is_pkg_local = true;
-            println!(
-                "Updating package from directory `{}`...",
-                pkg_path.display()
-            );
-            pkg_path
+        // Packages pinned to a commit keep track of its full hash
+        let reference = if ref_kind == Some(RefKind::Commit) {
+            commit.clone()
         } else {
-            println!("Updating package `{}@{}`...", &pkg_name, branch_tag);
+            pkg_tag
+        };
+        let origin = PkgOrigin {
+            provider: None,
+            reference,
+            ref_kind,
+            requirement,
+        };
+        Ok((origin, commit))
+    }
 
-            if !self.paket.pkg_exists(pkg_name) {
-                bail!(
-                "package `{}` is not installed. Try to use the `add` command to install it first.",
-                pkg_name
-                )
+    /// Replace the version requirement recorded on the installation receipt of a package.
+    fn set_receipt_requirement(&mut self, pkg_id: &str, requirement: Option<String>) {
+        if let Some(mut receipt) = self.receipts.get(pkg_id).cloned() {
+            if receipt.requirement != requirement {
+                receipt.requirement = requirement;
+                self.receipts.insert(pkg_id, receipt);
             }
+        }
+    }
 
-            // Fetch remote repository references and checkout
-            self.git.fetch(pkg_name, pkg_tag)?;
-            self.git.checkout(pkg_name, Some("FETCH_HEAD"))?;
+    /// Write the lockfile based on the current installation receipts.
+    fn write_lockfile(&self) -> Result {
+        Lockfile::from_receipts(&self.receipts).save(&self.paket.paths.paket_dir)
+    }
 
-            self.git
-                .base_dir
-                .join(pkg_name)
-                .canonicalize()
-                .with_context(|| format!("package `{}` was not updated properly.", pkg_name))?
+    /// Command action to install a new package and invoke a `paket_install` Fish shell event.
+    /// Several packages are cloned concurrently (up to `jobs` at once) and installed
+    /// continuing past failures and reporting a summary at the end.
+    /// Files of other packages or user files are only replaced when `force` is enabled.
+    pub fn install(
+        &mut self,
+        pkg_names: &[String],
+        git_provider: &str,
+        jobs: usize,
+        force: bool,
+    ) -> Result {
+        self.record_missing_receipts()?;
+        let pkg_fmts = plan_pkgs(pkg_names, PkgNameFmt::from)?;
+        if let [pkg_fmt] = pkg_fmts.as_slice() {
+            self.transaction(|a| a.install_pkg(pkg_fmt, git_provider, &mut vec![], force))?;
+            self.write_lockfile()?;
+
+            println!("Package was installed successfully.");
+            println!("Now just reload your current Fish shell session.");
+
+            return Ok(());
+        }
+
+        // Dependencies are installed before their dependents so listed packages
+        // could be already installed as dependencies of previous ones
+        let installed = self
+            .receipts
+            .packages
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let mut failed = self.prefetch(&pkg_fmts, jobs, |a, f| a.checkout_install(f, git_provider));
+
+        let mut results = vec![];
+        let pkg_fmts = pkg_fmts.iter().collect::<Vec<_>>();
+        for pkg_fmt in self.dependency_order(&pkg_fmts) {
+            let pkg_id = pkg_fmt.get_id();
+            let result = match failed.remove(&pkg_id) {
+                Some(err) => Err(err),
+                None => {
+                    self.transaction(|a| a.install_pkg(pkg_fmt, git_provider, &mut vec![], force))
+                }
+            };
+            let status = if !installed.contains(&pkg_id) && self.receipts.get(&pkg_id).is_some() {
+                "installed"
+            } else if let Err(err) = result {
+                println!("Error: package `{}` was not installed: {:#}", pkg_id, err);
+                "failed"
+            } else {
+                "installed"
+            };
+            results.push((pkg_id, status));
+        }
+        self.write_lockfile()?;
+
+        print_results("installed", &results)
+    }
+
+    /// Install a package along with its dependencies keeping track of the chain of
+    /// packages being installed in order to detect dependency cycles.
+    fn install_pkg(
+        &mut self,
+        pkg_fmt: &PkgNameFmt,
+        git_provider: &str,
+        stack: &mut Vec<String>,
+        force: bool,
+    ) -> Result {
+        // Use the package checkout prepared in advance (if any)
+        let checkout = match self.checkouts.remove(&pkg_fmt.get_id()) {
+            Some(checkout) => checkout,
+            None => self.checkout_install(pkg_fmt, git_provider)?,
         };
+        let PkgCheckout {
+            pkg_dir,
+            origin,
+            is_pkg_local,
+            ..
+        } = checkout;
+        if !is_pkg_local {
+            self.tx().cloned(&pkg_dir)?;
+        }
+
+        // Verify the package commit when an exact one was requested
+        if let Some(commit) = &pkg_fmt.pkg_commit {
+            let head_commit = self.git.head_commit(&pkg_dir)?;
+            if &head_commit != commit {
+                bail!(
+                    "package `{}` is at commit `{}` but commit `{}` was expected.",
+                    pkg_fmt.get_id(),
+                    head_commit,
+                    commit
+                );
+            }
+        }
 
         // Process Fish shell package structure and read the Packet manifest
         let manifest =
             self.paket
                 .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, is_pkg_local)?;
 
+        // Install missing dependencies first
+        stack.push(pkg_fmt.get_id());
+        let dependencies = self.install_dependencies(&pkg_dir, &manifest, stack, force)?;
+        stack.pop();
+
         if let Some(toml_pkg) = manifest.package {
             // Copy all corresponding package files to Fish shell directories
-            self.paket
-                .scan_pkg_dir(pkg_dir, &toml_pkg.include, |src, dest| {
-                    fs::copy(src, dest)?;
-                    Ok(())
-                })?;
+            let files = self.copy_pkg_files(
+                &pkg_fmt.get_id(),
+                &pkg_dir,
+                &toml_pkg.include,
+                &[],
+                force,
+                false,
+            )?;
+            self.save_receipt(pkg_fmt, &pkg_dir, &toml_pkg, origin, dependencies, files)?;
 
-            // Emit an `after_update` Fish shell event if there is an associated Paket event
+            // Emit an `after_install` Fish shell event if there is an associated Paket event
             if let Some(toml_events) = manifest.events {
                 self.paket
-                    .emit_event(&toml_pkg.name, &toml_events, PaketEvents::AfterUpdate)?;
+                    .emit_event(&toml_pkg.name, &toml_events, PaketEvents::AfterInstall)?;
             }
         } else {
             bail!("`paket.toml` file could not be parsed correctly.")
         };
 
-        println!("Package was updated successfully.");
-        println!("Now just reload your current Fish shell session.");
-
         Ok(())
     }
 
-    /// Command action to remove an existing package and invoke a `paket_uninstall` Fish shell event.
-    pub fn remove(&mut self, pkg_name: &str) -> Result {
-        let pkg_fmt = PkgNameFmt::from(pkg_name)?;
+    /// Prepare package checkouts concurrently using at most `jobs` worker threads so only
+    /// the network-bound Git operations run in parallel while copying files stays serialized.
+    /// Return the checkout errors keyed by package identifier.
+    fn prefetch<'f, I, F>(
+        &mut self,
+        pkg_fmts: I,
+        jobs: usize,
+        checkout: F,
+    ) -> BTreeMap<String, anyhow::Error>
+    where
+        I: IntoIterator<Item = &'f PkgNameFmt>,
+        F: Fn(&Self, &PkgNameFmt) -> Result<PkgCheckout> + Sync,
+    {
+        let pkg_fmts = pkg_fmts.into_iter().collect::<Vec<_>>();
+        let results = pool::map(jobs, &pkg_fmts, |pkg_fmt| checkout(self, pkg_fmt));
+
+        let mut failed = BTreeMap::new();
+        for (pkg_fmt, result) in pkg_fmts.into_iter().zip(results) {
+            match result {
+                Ok(pkg_checkout) => {
+                    self.checkouts.insert(pkg_fmt.get_id(), pkg_checkout);
+                }
+                Err(err) => {
+                    failed.insert(pkg_fmt.get_id(), err);
+                }
+            }
+        }
+        failed
+    }
+
+    /// Clone a remote package or check a local one before its installation.
+    /// This is the network-bound part of an installation which can run concurrently.
+    fn checkout_install(&self, pkg_fmt: &PkgNameFmt, git_provider: &str) -> Result<PkgCheckout> {
         let pkg_name = &pkg_fmt.get_short_name();
-        let pkg_path = pkg_fmt.get_pkg_path();
-        let is_pkg_path = pkg_path.is_some();
+        let mut origin = None;
+        let mut is_pkg_local = false;
 
         // Check for a local package (directory path) or a remote one
-        let pkg_dir = if is_pkg_path {
-            let pkg_path = pkg_path.unwrap_or_default();
+        let pkg_dir = if let Some(pkg_path) = pkg_fmt.get_pkg_path() {
+            is_pkg_local = true;
+
+            // Check if package dir path is a valid Git repository
+            self.git
+                .check_valid_repo(&pkg_path)
+                .with_context(|| "provided package directory is not a valid Git repository.")?;
+
             println!(
-                "Uninstalling package using directory `{}` as reference...",
+                "Installing package from directory `{}`...",
                 pkg_path.display()
             );
             pkg_path
         } else {
-            println!("Uninstalling package `{}`...", &pkg_name);
-
-            // Process Fish shell package structure
-            let pkg_dir = self.git.base_dir.join(pkg_name);
-            if !self.paket.pkg_exists(pkg_name) {
+            if self.paket.pkg_exists(pkg_name) {
                 bail!(
-                    "package `{}` is not installed or was already removed.",
+                    "package `{}` is already installed. Try to use the `up` command to upgrade it.",
                     pkg_name
                 );
             }
 
-            pkg_dir.canonicalize()?
-        };
+            let (endpoint, provider) = match pkg_fmt.get_pkg_url() {
+                Some(url) => (url.to_owned(), None),
+                None => (
+                    self.git.get_remote_endpoint(pkg_name, git_provider)?,
+                    Some(git_provider.to_owned()),
+                ),
+            };
+            // Resolve the package against its cached mirror refreshing it only when the
+            // requested reference is not there yet or it could have moved (unless offline)
+            let offline = self.paket.settings.offline();
+            let mirror_dir = self.cache.mirror_dir(&endpoint);
+            let is_cached = mirror_dir.is_dir();
+            if !is_cached {
+                self.fetch_mirror(&mirror_dir, &endpoint)?;
+            }
+            let pkg_commit = pkg_fmt.pkg_commit.as_deref();
+            let mut resolved = self.resolve_mirror_ref(&mirror_dir, &pkg_fmt.pkg_tag, pkg_commit);
+            let is_stale = match &resolved {
+                // Branches and version requirements follow the remote repository
+                Ok((origin, _)) => {
+                    origin.ref_kind == Some(RefKind::Branch) || origin.requirement.is_some()
+                }
+                Err(_) => true,
+            };
+            if is_cached && !offline && is_stale {
+                self.fetch_mirror(&mirror_dir, &endpoint)?;
+                resolved = self.resolve_mirror_ref(&mirror_dir, &pkg_fmt.pkg_tag, pkg_commit);
+            }
+            let (mut pkg_origin, commit) = resolved.with_context(|| {
+                if offline {
+                    format!(
+                        "package `{}` is not available on the repository cache of `{}`.",
+                        pkg_id_with_tag(pkg_fmt),
+                        endpoint
+                    )
+                } else {
+                    format!(
+                        "package `{}` could not be resolved on `{}`.",
+                        pkg_name, endpoint
+                    )
+                }
+            })?;
 
-        // Process Fish shell package structure and read the Packet manifest
-        let manifest =
-            self.paket
-                .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, is_pkg_path)?;
+            print_checkout("Installing", pkg_name, &pkg_origin);
+            let pkg_dir = self.git.base_dir.join(pkg_name);
+            if let Err(err) =
+                self.git
+                    .checkout_from_mirror(pkg_name, &mirror_dir, &endpoint, &commit)
+            {
+                // Drop the partial clone
+                fs::remove_dir_all(&pkg_dir).ok();
+                return Err(err);
+            }
 
-        if let Some(toml_pkg) = manifest.package {
-            // Emit an `after_update` Fish shell event if there is an associated Paket event
-            if let Some(toml_events) = manifest.events {
-                self.paket.emit_event(
-                    &toml_pkg.name,
-                    &toml_events,
-                    PaketEvents::BeforeUninstall,
-                )?;
+            if !self.paket.pkg_exists(pkg_name) {
+                bail!("package `{}` was not cloned with success.", pkg_name);
+            }
+            pkg_origin.provider = provider;
+            origin = Some(pkg_origin);
+            pkg_dir
+        };
+
+        Ok(PkgCheckout {
+            pkg_dir,
+            origin,
+            is_pkg_local,
+            up_to_date: false,
+        })
+    }
+
+    /// Sort packages so every one comes after the given packages it depends on
+    /// reading their manifests from their prepared checkouts.
+    /// Packages depending on each other in a cycle keep their original order.
+    fn dependency_order<'f>(&self, pkg_fmts: &[&'f PkgNameFmt]) -> Vec<&'f PkgNameFmt> {
+        let ids = pkg_fmts.iter().map(|f| f.get_id()).collect::<BTreeSet<_>>();
+        let dependencies = pkg_fmts
+            .iter()
+            .map(|f| {
+                let pkg_id = f.get_id();
+                let deps = match self.checkouts.get(&pkg_id) {
+                    Some(checkout) => self.manifest_dependencies(&checkout.pkg_dir),
+                    None => vec![],
+                };
+                (pkg_id, deps)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut sorted: Vec<&PkgNameFmt> = vec![];
+        let mut pending = pkg_fmts.to_vec();
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|f| {
+                dependencies[&f.get_id()]
+                    .iter()
+                    .all(|d| !ids.contains(d) || sorted.iter().any(|s| &s.get_id() == d))
+            });
+            if ready.is_empty() {
+                sorted.extend(rest);
+                break;
+            }
+            sorted.extend(ready);
+            pending = rest;
+        }
+
+        sorted
+    }
+
+    /// Return the identifiers of the dependencies declared by the manifest of a package
+    /// directory skipping the ones which can not be resolved.
+    fn manifest_dependencies(&self, pkg_dir: &Path) -> Vec<String> {
+        let toml_deps = self
+            .paket
+            .read_pkg_dir_with_manifest(pkg_dir, "", true)
+            .ok()
+            .and_then(|m| m.dependencies)
+            .unwrap_or_default();
+        toml_deps
+            .iter()
+            .filter_map(|(dep_name, toml_dep)| {
+                PkgNameFmt::from_dependency(dep_name, toml_dep, pkg_dir, &self.git).ok()
+            })
+            .map(|(dep_fmt, _)| dep_fmt.get_id())
+            .collect()
+    }
+
+    /// Install every missing dependency of a package manifest transitively
+    /// returning the identifiers of all its direct dependencies.
+    fn install_dependencies(
+        &mut self,
+        pkg_dir: &Path,
+        manifest: &TomlManifest,
+        stack: &mut Vec<String>,
+        force: bool,
+    ) -> Result<Vec<String>> {
+        let mut dependencies = vec![];
+        let toml_deps = match &manifest.dependencies {
+            Some(toml_deps) => toml_deps,
+            None => return Ok(dependencies),
+        };
+
+        for (dep_name, toml_dep) in toml_deps {
+            let (dep_fmt, dep_provider) =
+                PkgNameFmt::from_dependency(dep_name, toml_dep, pkg_dir, &self.git)
+                    .with_context(|| format!("dependency `{}` could not be resolved.", dep_name))?;
+            let dep_id = dep_fmt.get_id();
+
+            // Check for cyclic dependencies
+            if let Some(pos) = stack.iter().position(|id| id == &dep_id) {
+                let mut cycle = stack[pos..].to_vec();
+                cycle.push(dep_id);
+                bail!("dependency cycle detected: {}", cycle.join(" -> "));
+            }
+
+            // Repositories which are just prepared checkouts are not installed yet
+            let is_installed = self.receipts.get(&dep_id).is_some()
+                || (dep_fmt.get_pkg_path().is_none()
+                    && self.paket.pkg_exists(&dep_id)
+                    && !self.checkouts.contains_key(&dep_id));
+            if is_installed {
+                println!("Dependency `{}` is already installed.", dep_id);
+            } else {
+                println!(
+                    "Installing dependency `{}` required by `{}`...",
+                    dep_id,
+                    stack.last().map(|s| s.as_str()).unwrap_or_default()
+                );
+                self.install_pkg(&dep_fmt, &dep_provider, stack, force)?;
+            }
+
+            dependencies.push(dep_id);
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Command action to install every package recorded in the lockfile.
+    /// When `locked` is enabled, packages are installed using their exact commits
+    /// failing if either a commit or the installed files checksum doesn't match.
+    pub fn install_from_lockfile(&mut self, locked: bool) -> Result {
+        self.record_missing_receipts()?;
+        let lockfile = Lockfile::load(&self.paket.paths.paket_dir)?;
+
+        // Check that every missing package is available on the repository cache first
+        if self.paket.settings.offline() {
+            let missing = lockfile
+                .sorted_packages()
+                .into_iter()
+                .filter(|p| self.receipts.get(&p.id).is_none())
+                .filter_map(|p| self.missing_from_cache(p, locked))
+                .collect::<Vec<_>>();
+            check_missing(&missing)?;
+        }
+
+        for locked_pkg in lockfile.sorted_packages() {
+            self.transaction(|a| a.install_locked_pkg(locked_pkg, locked))?;
+        }
+
+        self.write_lockfile()?;
+
+        println!("Packages were installed successfully.");
+        println!("Now just reload your current Fish shell session.");
+
+        Ok(())
+    }
+
+    /// Return why a package recorded in the lockfile is not available on the repository cache
+    /// (if so). Local packages are always available.
+    fn missing_from_cache(&self, locked_pkg: &LockedPackage, locked: bool) -> Option<String> {
+        let url = match &locked_pkg.source {
+            ReceiptSource::Remote { url } => url,
+            ReceiptSource::Local { .. } => return None,
+        };
+        let mirror_dir = self.cache.mirror_dir(url);
+        if !mirror_dir.is_dir() {
+            return Some(format!(
+                "{}: repository `{}` is not cached",
+                locked_pkg.id, url
+            ));
+        }
+
+        let reference = if locked {
+            locked_pkg.commit.as_deref()
+        } else {
+            locked_pkg.reference.as_deref()
+        };
+        match reference {
+            Some(reference) if self.git.resolve_commit(&mirror_dir, reference).is_err() => Some(
+                format!("{}: reference `{}` is not cached", locked_pkg.id, reference),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Install a single package recorded in the lockfile.
+    fn install_locked_pkg(&mut self, locked_pkg: &LockedPackage, locked: bool) -> Result {
+        let pkg_id = &locked_pkg.id;
+
+        if let Some(receipt) = self.receipts.get(pkg_id) {
+            if locked && receipt.commit != locked_pkg.commit {
+                bail!(
+                    "package `{}` is already installed at commit `{}` but the lockfile requires commit `{}`.",
+                    pkg_id,
+                    receipt.commit.as_deref().unwrap_or("-"),
+                    locked_pkg.commit.as_deref().unwrap_or("-")
+                );
+            }
+            println!("Package `{}` is already installed.", pkg_id);
+            return Ok(());
+        }
+
+        let (mut pkg_fmt, git_provider) = match &locked_pkg.source {
+            ReceiptSource::Local { path } => (
+                PkgNameFmt::from(&path.to_string_lossy())?,
+                self.git.default_provider.clone(),
+            ),
+            ReceiptSource::Remote { url } => {
+                // Packages installed via a Git host provider use the
+                // `username/package_name` format and the rest their Git URL
+                let (mut pkg_fmt, provider) = match &locked_pkg.provider {
+                    Some(provider) => (PkgNameFmt::from(pkg_id)?, provider.clone()),
+                    None => match self.git.get_provider_from_url(url) {
+                        Ok((provider, user_repo_name)) if &user_repo_name == pkg_id => {
+                            (PkgNameFmt::from(pkg_id)?, provider)
+                        }
+                        _ => (PkgNameFmt::from(url)?, self.git.default_provider.clone()),
+                    },
+                };
+                if let Some(reference) = &locked_pkg.reference {
+                    pkg_fmt.pkg_tag = reference.clone();
+                }
+                (pkg_fmt, provider)
+            }
+        };
+        if locked {
+            if locked_pkg.commit.is_none() {
+                bail!("package `{}` has no locked commit.", pkg_id);
+            }
+            pkg_fmt.pkg_commit = locked_pkg.commit.clone();
+        }
+
+        self.install_pkg(&pkg_fmt, &git_provider, &mut vec![], false)?;
+
+        // Keep tracking the locked version requirement on future updates
+        self.set_receipt_requirement(pkg_id, locked_pkg.requirement.clone());
+
+        // Verify that installed files match the locked ones
+        if locked {
+            let checksum = self
+                .receipts
+                .get(pkg_id)
+                .map(|r| r.checksum())
+                .unwrap_or_default();
+            if checksum != locked_pkg.checksum {
+                bail!(
+                    "package `{}` installed files checksum `{}` doesn't match the locked checksum `{}`.",
+                    pkg_id,
+                    checksum,
+                    locked_pkg.checksum
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Command action to update existing packages or all installed ones if no one is given.
+    /// Several packages are updated continuing past failures and reporting a summary at the end.
+    /// Files of other packages or user files are only replaced when `force` is enabled.
+    pub fn update(
+        &mut self,
+        pkg_names: &[String],
+        jobs: usize,
+        on_modified: OnModified,
+        force: bool,
+    ) -> Result {
+        self.record_missing_receipts()?;
+        if let [pkg_name] = pkg_names {
+            let pkg_fmt = PkgNameFmt::from(pkg_name)?;
+            let updated = self.transaction(|a| a.update_pkg(&pkg_fmt, on_modified, force))?;
+            self.write_lockfile()?;
+            if !updated {
+                return Ok(());
+            }
+
+            println!("Package was updated successfully.");
+            println!("Now just reload your current Fish shell session.");
+
+            return Ok(());
+        }
+
+        let pkg_names = if pkg_names.is_empty() {
+            // Linked packages always reflect their local directories
+            self.receipts
+                .packages
+                .iter()
+                .filter(|(_, r)| !r.is_linked())
+                .map(|(id, _)| id.to_owned())
+                .collect()
+        } else {
+            pkg_names.to_vec()
+        };
+        if pkg_names.is_empty() {
+            if self.receipts.packages.is_empty() {
+                println!("No packages installed.");
+            } else {
+                println!("No packages to update.");
+            }
+            return Ok(());
+        }
+
+        // Fetch the packages concurrently (up to `jobs` at once) but update them one by one
+        let pkg_fmts = pkg_names
+            .iter()
+            .map(|pkg_name| (pkg_name, PkgNameFmt::from(pkg_name)))
+            .collect::<Vec<_>>();
+        let valid_fmts = pkg_fmts.iter().filter_map(|(_, f)| f.as_ref().ok());
+        let mut failed = self.prefetch(valid_fmts, jobs, |a, f| a.checkout_update(f));
+
+        let mut results = vec![];
+        for (pkg_name, pkg_fmt) in pkg_fmts {
+            let result = pkg_fmt.and_then(|f| match failed.remove(&f.get_id()) {
+                Some(err) => Err(err),
+                None => self.transaction(|a| a.update_pkg(&f, on_modified, force)),
+            });
+            let status = match result {
+                Ok(true) => "updated",
+                Ok(false) => "unchanged",
+                Err(err) => {
+                    println!("Error: package `{}` was not updated: {:#}", pkg_name, err);
+                    "failed"
+                }
+            };
+            results.push((pkg_name.to_owned(), status));
+        }
+        self.write_lockfile()?;
+
+        print_results("updated", &results)
+    }
+
+    /// Update an installed package installing its new dependencies if any.
+    /// Return `false` if the package is installed from a fixed Git tag which is already up to date.
+    fn update_pkg(
+        &mut self,
+        pkg_fmt: &PkgNameFmt,
+        on_modified: OnModified,
+        force: bool,
+    ) -> Result<bool> {
+        let pkg_id = pkg_fmt.get_id();
+        if self.receipts.get(&pkg_id).is_some_and(|r| r.is_linked()) {
+            bail!(
+                "package `{}` is linked. Use the `link` command to refresh its links instead.",
+                pkg_id
+            );
+        }
+
+        // Use the package checkout prepared in advance (if any)
+        let checkout = match self.checkouts.remove(&pkg_id) {
+            Some(checkout) => checkout,
+            None => self.checkout_update(pkg_fmt)?,
+        };
+        let PkgCheckout {
+            pkg_dir,
+            origin,
+            is_pkg_local,
+            up_to_date,
+        } = checkout;
+
+        // Keep tracking the requested version requirement of an up to date package
+        if up_to_date {
+            if let Some(origin) = origin {
+                self.set_receipt_requirement(&pkg_id, origin.requirement);
+            }
+            return Ok(false);
+        }
+        if !is_pkg_local {
+            if let Some(commit) = self.receipts.get(&pkg_id).and_then(|r| r.commit.clone()) {
+                self.tx().checked_out(&pkg_id, &commit)?;
+            }
+        }
+
+        // Process Fish shell package structure and read the Packet manifest
+        let manifest =
+            self.paket
+                .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, is_pkg_local)?;
+
+        // Install dependencies which could be added by the new package version
+        let dependencies =
+            self.install_dependencies(&pkg_dir, &manifest, &mut vec![pkg_fmt.get_id()], force)?;
+
+        if let Some(toml_pkg) = manifest.package {
+            // Copy all corresponding package files to Fish shell directories
+            // and remove the previous ones no longer provided by the package
+            let previous_files = self
+                .receipts
+                .get(&pkg_id)
+                .map(|r| r.files.clone())
+                .unwrap_or_default();
+            let kept_files = self.protect_modified_files(&pkg_id, &previous_files, on_modified)?;
+            let mut files = self.copy_pkg_files(
+                &pkg_id,
+                &pkg_dir,
+                &toml_pkg.include,
+                &kept_files,
+                force,
+                false,
+            )?;
+            for file in &previous_files {
+                if !files.iter().any(|f| f.path == file.path)
+                    && !kept_files.contains(file)
+                    && self.receipts.owner(&file.path, &pkg_id).is_none()
+                {
+                    self.remove_pkg_file(file)?;
+                }
+            }
+
+            // Skipped files are still tracked so their modifications keep being detected
+            files.extend(kept_files);
+            self.print_file_changes(&pkg_id, &previous_files, &files);
+            self.save_receipt(pkg_fmt, &pkg_dir, &toml_pkg, origin, dependencies, files)?;
+
+            // Emit an `after_update` Fish shell event if there is an associated Paket event
+            if let Some(toml_events) = manifest.events {
+                self.paket
+                    .emit_event(&toml_pkg.name, &toml_events, PaketEvents::AfterUpdate)?;
+            }
+        } else {
+            bail!("`paket.toml` file could not be parsed correctly.")
+        };
+
+        Ok(true)
+    }
+
+    /// Fetch the new version of an installed remote package or check a local one before its update.
+    /// This is the network-bound part of an update which can run concurrently.
+    fn checkout_update(&self, pkg_fmt: &PkgNameFmt) -> Result<PkgCheckout> {
+        let pkg_name = &pkg_fmt.get_short_name();
+        let mut origin = None;
+        let mut is_pkg_local = false;
+
+        // Check for a local package (directory path) or a remote one
+        let pkg_dir = if let Some(pkg_path) = pkg_fmt.get_pkg_path() {
+            is_pkg_local = true;
+            println!(
+                "Updating package from directory `{}`...",
+                pkg_path.display()
+            );
+            pkg_path
+        } else {
+            if !self.paket.pkg_exists(pkg_name) {
+                bail!(
+                "package `{}` is not installed. Try to use the `add` command to install it first.",
+                pkg_name
+                )
+            }
+
+            // Keep tracking the installed version requirement, branch or tag if no one was requested
+            let pkg_id = pkg_fmt.get_id();
+            let receipt = self.receipts.get(&pkg_id).cloned();
+
+            // Packages pinned to an exact commit are only updated when a new reference is given
+            if let Some(r) = receipt
+                .as_ref()
+                .filter(|r| r.ref_kind == Some(RefKind::Commit))
+            {
+                if pkg_fmt.pkg_tag.trim().is_empty() {
+                    println!(
+                        "Package `{}` is pinned to commit `{}`. Use `{}@<ref>` to update it.",
+                        &pkg_name,
+                        r.reference.as_deref().unwrap_or("-"),
+                        &pkg_name
+                    );
+                    return Ok(PkgCheckout {
+                        pkg_dir: self.git.base_dir.join(pkg_name),
+                        origin: None,
+                        is_pkg_local,
+                        up_to_date: true,
+                    });
+                }
+            }
+
+            let installed_ref = receipt
+                .as_ref()
+                .and_then(|r| r.requirement.clone().or_else(|| r.reference.clone()));
+            let pkg_tag = match installed_ref {
+                Some(installed_ref) if pkg_fmt.pkg_tag.trim().is_empty() => installed_ref,
+                _ => pkg_fmt.pkg_tag.clone(),
+            };
+
+            // A fixed Git tag or commit can not move so there is nothing to update
+            let is_fixed = |reference: &str| {
+                receipt.as_ref().is_some_and(|r| {
+                    r.reference.as_deref() == Some(reference)
+                        && matches!(r.ref_kind, Some(RefKind::Tag | RefKind::Commit))
+                })
+            };
+            let up_to_date = |origin: PkgOrigin| {
+                println!(
+                    "Package `{}@{}` is already up to date.",
+                    &pkg_name, origin.reference
+                );
+                PkgCheckout {
+                    pkg_dir: self.git.base_dir.join(pkg_name),
+                    origin: Some(origin),
+                    is_pkg_local,
+                    up_to_date: true,
+                }
+            };
+            if fmt::parse_version_req(&pkg_tag)?.is_none() && is_fixed(&pkg_tag) {
+                return Ok(up_to_date(PkgOrigin {
+                    provider: None,
+                    reference: pkg_tag,
+                    ref_kind: receipt.as_ref().and_then(|r| r.ref_kind),
+                    requirement: None,
+                }));
+            }
+
+            // Refresh the cached mirror (unless offline) and resolve the package against it
+            let endpoint = self.git.remote_url(&self.git.base_dir.join(pkg_name))?;
+            let mirror_dir = self.cache.mirror_dir(&endpoint);
+            if !self.paket.settings.offline() || !mirror_dir.is_dir() {
+                self.fetch_mirror(&mirror_dir, &endpoint)?;
             }
+            let (mut pkg_origin, commit) = self
+                .resolve_mirror_ref(&mirror_dir, &pkg_tag, None)
+                .with_context(|| {
+                    format!(
+                        "package `{}` could not be resolved on `{}`.",
+                        pkg_name, endpoint
+                    )
+                })?;
+            // A branch without new commits is up to date as well
+            if is_installed_commit(receipt.as_ref(), &pkg_origin.reference, &commit)
+                || (pkg_origin.ref_kind != Some(RefKind::Branch) && is_fixed(&pkg_origin.reference))
+            {
+                return Ok(up_to_date(pkg_origin));
+            }
+
+            print_checkout("Updating", pkg_name, &pkg_origin);
+            self.git
+                .checkout_from_mirror(pkg_name, &mirror_dir, &endpoint, &commit)?;
+
+            pkg_origin.provider = receipt.and_then(|r| r.provider);
+            origin = Some(pkg_origin);
+            self.git
+                .base_dir
+                .join(pkg_name)
+                .canonicalize()
+                .with_context(|| format!("package `{}` was not updated properly.", pkg_name))?
+        };
+
+        Ok(PkgCheckout {
+            pkg_dir,
+            origin,
+            is_pkg_local,
+            up_to_date: false,
+        })
+    }
+
+    /// Command action to remove an existing package and invoke a `paket_uninstall` Fish shell event.
+    /// Several packages are removed (dependents first) continuing past failures
+    /// and reporting a summary at the end.
+    pub fn remove(&mut self, pkg_names: &[String], force: bool, on_modified: OnModified) -> Result {
+        self.record_missing_receipts()?;
+        let mut pkg_fmts = plan_pkgs(pkg_names, |n| self.parse_installed_pkg(n))?;
+        if let [pkg_fmt] = pkg_fmts.as_slice() {
+            self.transaction(|a| a.remove_pkg(pkg_fmt, force, on_modified))?;
+            self.write_lockfile()?;
+
+            println!("Package was uninstalled successfully.");
+            println!("Now just reload your current Fish shell session.");
 
-            // Remove all corresponding package files from Fish shell directories
+            return Ok(());
+        }
+
+        let pkg_ids = pkg_fmts.iter().map(|f| f.get_id()).collect::<Vec<_>>();
+        let pkg_ids = self.receipts.removal_order(pkg_ids);
+        pkg_fmts.sort_by_key(|f| pkg_ids.iter().position(|id| id == &f.get_id()));
+
+        let mut results = vec![];
+        for pkg_fmt in &pkg_fmts {
+            let pkg_id = pkg_fmt.get_id();
+            let status = match self.transaction(|a| a.remove_pkg(pkg_fmt, force, on_modified)) {
+                Ok(()) => "removed",
+                Err(err) => {
+                    println!("Error: package `{}` was not removed: {:#}", pkg_id, err);
+                    "failed"
+                }
+            };
+            results.push((pkg_id, status));
+        }
+        self.write_lockfile()?;
+
+        print_results("removed", &results)
+    }
+
+    /// Uninstall an installed package unless other installed packages depend on it
+    /// or `force` is enabled.
+    fn remove_pkg(&mut self, pkg_fmt: &PkgNameFmt, force: bool, on_modified: OnModified) -> Result {
+        let pkg_id = pkg_fmt.get_id();
+        let pkg_name = &pkg_fmt.get_short_name();
+        let pkg_path = pkg_fmt.get_pkg_path();
+        let is_pkg_path = pkg_path.is_some();
+        let receipt = self.receipts.get(&pkg_id).cloned();
+
+        // Check for installed packages depending on the current one
+        let dependents = self.receipts.dependents(&pkg_id);
+        if !dependents.is_empty() {
+            let dependents = dependents.join("`, `");
+            if !force {
+                bail!(
+                    "package `{}` is required by `{}`. Use `--force` to remove it anyway.",
+                    pkg_id,
+                    dependents
+                );
+            }
+            println!(
+                "Warning: package `{}` is still required by `{}`.",
+                pkg_id, dependents
+            );
+        }
+
+        // Check for a local package (directory path) or a remote one
+        let pkg_dir = if is_pkg_path {
+            let pkg_path = pkg_path.unwrap_or_default();
+            println!(
+                "Uninstalling package using directory `{}` as reference...",
+                pkg_path.display()
+            );
+            pkg_path
+        } else {
+            println!("Uninstalling package `{}`...", &pkg_name);
+
+            if !self.paket.pkg_exists(pkg_name) && receipt.is_none() {
+                bail!(
+                    "package `{}` is not installed or was already removed.",
+                    pkg_name
+                );
+            }
+
+            self.git.base_dir.join(pkg_name)
+        };
+
+        // Process Fish shell package structure and read the Packet manifest.
+        // The manifest is only required when there is no installation receipt.
+        let manifest = if receipt.is_none() || pkg_dir.is_dir() {
+            let manifest =
+                self.paket
+                    .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, is_pkg_path)?;
+            match manifest.package {
+                Some(toml_pkg) => Some((toml_pkg, manifest.events)),
+                None => bail!("`paket.toml` file could not be parsed correctly."),
+            }
+        } else {
+            None
+        };
+
+        // Check for locally modified files before changing anything
+        let kept_files = match &receipt {
+            Some(receipt) => self.protect_modified_files(&pkg_id, &receipt.files, on_modified)?,
+            None => vec![],
+        };
+
+        // Emit a `before_uninstall` Fish shell event if there is an associated Paket event
+        if let Some((toml_pkg, Some(toml_events))) = &manifest {
+            self.paket
+                .emit_event(&toml_pkg.name, toml_events, PaketEvents::BeforeUninstall)?;
+        }
+
+        // Remove all corresponding package files from Fish shell directories
+        if let Some(receipt) = &receipt {
+            for file in &receipt.files {
+                if !kept_files.contains(file) {
+                    self.remove_pkg_file(file)?;
+                }
+            }
+            self.forget_displaced_files(&pkg_id)?;
+        } else if let Some((toml_pkg, _)) = &manifest {
+            // Packages installed without a receipt are removed by scanning their directory
             self.paket
                 .scan_pkg_dir(pkg_dir.clone(), &toml_pkg.include, |_, dest| {
                     if dest.exists() {
@@ -216,22 +1620,854 @@ impl<'a> Actions<'a> {
                     }
                     Ok(())
                 })?;
+        }
+
+        if !is_pkg_path && pkg_dir.exists() {
+            // The package repository is just a checkout of its cached mirror
+            // so installing it again later doesn't require to clone it again
+            self.tx().remove_repo(&pkg_dir)?;
+        }
+
+        self.receipts.remove(&pkg_id);
+
+        Ok(())
+    }
+
+    /// Command action to symlink the files of a local package directory into Fish shell
+    /// directories so its changes take effect without updating it.
+    /// Linking an already linked package refreshes its links.
+    pub fn link(&mut self, pkg_dir: &Path, force: bool) -> Result {
+        self.record_missing_receipts()?;
+        let pkg_fmt = local_pkg_fmt(pkg_dir)?;
+        let changed = self.transaction(|a| a.link_pkg(&pkg_fmt, force))?;
+        self.write_lockfile()?;
+        if !changed {
+            println!("Package links are already up to date.");
+            return Ok(());
+        }
+
+        println!("Package was linked successfully.");
+        println!("Now just reload your current Fish shell session.");
+
+        Ok(())
+    }
+
+    /// Link the files of a local package installing its missing dependencies first.
+    /// Return `false` if the package was already linked and none of its files changed.
+    fn link_pkg(&mut self, pkg_fmt: &PkgNameFmt, force: bool) -> Result<bool> {
+        let pkg_id = pkg_fmt.get_id();
+        let pkg_dir = pkg_fmt.get_pkg_path().unwrap_or_default();
+        let previous = self.receipts.get(&pkg_id).cloned();
+        let is_relink = match &previous {
+            Some(receipt) if !receipt.is_linked() => bail!(
+                "package `{}` is already installed by copying its files. Remove it first to link it.",
+                pkg_id
+            ),
+            Some(_) => true,
+            None => false,
+        };
+
+        if !is_relink {
+            println!("Linking package from directory `{}`...", pkg_dir.display());
+        }
+
+        // Process Fish shell package structure and read the Packet manifest
+        let manifest = self
+            .paket
+            .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, true)?;
+
+        // Install missing dependencies first
+        let dependencies =
+            self.install_dependencies(&pkg_dir, &manifest, &mut vec![pkg_id.clone()], force)?;
+
+        let toml_pkg = match manifest.package {
+            Some(toml_pkg) => toml_pkg,
+            None => bail!("`paket.toml` file could not be parsed correctly."),
+        };
+
+        // Link all corresponding package files into Fish shell directories
+        // and remove the previous links of files no longer provided by the package
+        let previous_files = previous.map(|r| r.files).unwrap_or_default();
+        let files = self.copy_pkg_files(&pkg_id, &pkg_dir, &toml_pkg.include, &[], force, true)?;
+        for file in &previous_files {
+            if !files.iter().any(|f| f.path == file.path) {
+                self.remove_pkg_file(file)?;
+            }
+        }
+        let changed = !is_relink || previous_files != files;
+        if is_relink && changed {
+            self.print_file_changes(&pkg_id, &previous_files, &files);
+        }
+        self.save_receipt(pkg_fmt, &pkg_dir, &toml_pkg, None, dependencies, files)?;
+
+        // Emit an `after_install` Fish shell event if there is an associated Paket event
+        if let (false, Some(toml_events)) = (is_relink, manifest.events) {
+            self.paket
+                .emit_event(&toml_pkg.name, &toml_events, PaketEvents::AfterInstall)?;
+        }
+
+        Ok(changed)
+    }
+
+    /// Command action to link a local package and keep its links in sync with the changes
+    /// of its directory (including new files) until it's interrupted.
+    pub fn dev(&mut self, pkg_dir: &Path, force: bool) -> Result {
+        self.record_missing_receipts()?;
+        let pkg_fmt = local_pkg_fmt(pkg_dir)?;
+        let pkg_id = pkg_fmt.get_id();
+        let mut watcher = Watcher::new(&pkg_fmt.get_pkg_path().unwrap_or_default())?;
+
+        self.transaction(|a| a.link_pkg(&pkg_fmt, force))?;
+        self.write_lockfile()?;
+        println!(
+            "Watching package directory `{}` for changes. Press Ctrl+C to stop.",
+            pkg_id
+        );
+
+        loop {
+            watcher.wait(DEV_DEBOUNCE)?;
+            match self.transaction(|a| a.link_pkg(&pkg_fmt, force)) {
+                Ok(true) => {
+                    self.write_lockfile()?;
+                    println!("Package files were synced.");
+                }
+                Ok(false) => {}
+                Err(err) => println!("Error: package `{}` could not be synced: {:#}", pkg_id, err),
+            }
+        }
+    }
+
+    /// Command action to remove the symbolic links of a linked local package.
+    /// Files which are no longer links (e.g replaced by hand) are kept.
+    pub fn unlink(&mut self, pkg_dir: &Path) -> Result {
+        // The package directory could be already gone
+        let pkg_dir = absolute_path(pkg_dir)?;
+
+        self.transaction(|a| a.unlink_pkg(&pkg_dir))?;
+        self.write_lockfile()?;
+
+        println!("Package was unlinked successfully.");
+        println!("Now just reload your current Fish shell session.");
+
+        Ok(())
+    }
+
+    /// Remove the links of a linked local package along with its receipt.
+    fn unlink_pkg(&mut self, pkg_dir: &Path) -> Result {
+        let pkg_id = pkg_dir.display().to_string();
+        let receipt = match self.receipts.get(&pkg_id) {
+            Some(receipt) if receipt.is_linked() => receipt.clone(),
+            Some(_) => bail!(
+                "package `{}` is not linked. Use the `rm` command to remove it instead.",
+                pkg_id
+            ),
+            None => bail!("package `{}` is not linked.", pkg_id),
+        };
+
+        println!("Unlinking package from directory `{}`...", pkg_id);
+
+        // Emit a `before_uninstall` Fish shell event if there is an associated Paket event
+        if pkg_dir.is_dir() {
+            let manifest = self.paket.read_pkg_dir_with_manifest(pkg_dir, "", true)?;
+            if let (Some(toml_pkg), Some(toml_events)) = (manifest.package, manifest.events) {
+                self.paket.emit_event(
+                    &toml_pkg.name,
+                    &toml_events,
+                    PaketEvents::BeforeUninstall,
+                )?;
+            }
+        }
+
+        for file in &receipt.files {
+            if file.linked && file.path.is_symlink() {
+                self.remove_pkg_file(file)?;
+            } else if file.path.exists() {
+                println!(
+                    "Warning: file `{}` is no longer a link and was kept.",
+                    file.path.display()
+                );
+            }
+        }
+        self.forget_displaced_files(&pkg_id)?;
+
+        self.receipts.remove(&pkg_id);
+
+        Ok(())
+    }
+
+    /// Parse a package name looking up the installation receipts first so installed
+    /// local packages are found even if their directories no longer exist.
+    fn parse_installed_pkg(&self, pkg_name: &str) -> Result<PkgNameFmt> {
+        let pkg_path = absolute_path(Path::new(pkg_name))?;
+        let receipt = self.receipts.get(&pkg_path.display().to_string());
+        if let Some(ReceiptSource::Local { path }) = receipt.map(|r| &r.source) {
+            if !path.is_dir() {
+                return PkgNameFmt::from_local_path(path.clone());
+            }
+        }
+        PkgNameFmt::from(pkg_name)
+    }
+
+    /// Command action to list all installed packages.
+    pub fn list(&self, json: bool) -> Result {
+        let entries = self
+            .receipts
+            .packages
+            .iter()
+            .map(|(id, receipt)| {
+                let (source, url, path) = match &receipt.source {
+                    ReceiptSource::Remote { url } => ("remote", Some(url.as_str()), None),
+                    ReceiptSource::Local { path } if receipt.is_linked() => {
+                        ("linked", None, Some(path.as_path()))
+                    }
+                    ReceiptSource::Local { path } => ("local", None, Some(path.as_path())),
+                };
+                ListEntry {
+                    id,
+                    name: &receipt.name,
+                    version: receipt.version.to_string(),
+                    source,
+                    url,
+                    path,
+                    reference: receipt.reference.as_deref(),
+                    commit: receipt.commit.as_deref(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if json {
+            let out = serde_json::to_string_pretty(&entries)
+                .with_context(|| "installed packages could not be serialized.")?;
+            println!("{}", out);
+            return Ok(());
+        }
+
+        if entries.is_empty() {
+            println!("No packages installed.");
+            return Ok(());
+        }
+
+        let rows = entries
+            .iter()
+            .map(|e| {
+                vec![
+                    e.name.to_owned(),
+                    e.version.clone(),
+                    e.id.to_owned(),
+                    e.reference.unwrap_or("-").to_owned(),
+                    e.commit
+                        .map(|c| c.chars().take(7).collect())
+                        .unwrap_or("-".into()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        table::print(&["NAME", "VERSION", "SOURCE", "REF", "COMMIT"], &rows);
+
+        Ok(())
+    }
+
+    /// Command action to report the installed remote packages which can be updated
+    /// comparing them against their remote repositories.
+    /// It fails if there are outdated packages.
+    pub fn outdated(&self, json: bool) -> Result {
+        if self.paket.settings.offline() {
+            bail!(
+                "the `outdated` command requires network access so it can not run in offline mode."
+            );
+        }
+
+        let mut entries = vec![];
+        for (id, receipt) in &self.receipts.packages {
+            let url = match &receipt.source {
+                ReceiptSource::Remote { url } => url,
+                ReceiptSource::Local { .. } => continue,
+            };
+            match self.outdated_entry(id, receipt, url) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    // Keep the report on the standard output parseable
+                    eprintln!("Warning: package `{}` could not be checked: {}", id, err);
+                    entries.push(OutdatedEntry {
+                        id,
+                        current: receipt.reference.clone().unwrap_or("-".into()),
+                        wanted: "-".into(),
+                        latest: None,
+                        outdated: false,
+                        error: Some(err.to_string().trim().to_owned()),
+                    });
+                }
+            }
+        }
+
+        let outdated = entries.iter().filter(|e| e.outdated).count();
+        let failed = entries.iter().filter(|e| e.error.is_some()).count();
+        if json {
+            let out = serde_json::to_string_pretty(&entries)
+                .with_context(|| "outdated packages could not be serialized.")?;
+            println!("{}", out);
+        } else if outdated == 0 && failed == 0 {
+            println!("All packages are up to date.");
+        } else if outdated == 0 {
+            println!("No checked packages can be updated.");
         } else {
-            bail!("`paket.toml` file could not be parsed correctly.")
+            let rows = entries
+                .iter()
+                .filter(|e| e.outdated)
+                .map(|e| {
+                    vec![
+                        e.id.to_owned(),
+                        e.current.clone(),
+                        e.wanted.clone(),
+                        e.latest.clone().unwrap_or("-".into()),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            table::print(&["PACKAGE", "CURRENT", "WANTED", "LATEST"], &rows);
+        }
+
+        if failed > 0 {
+            bail!("{} installed package(s) could not be checked.", failed);
+        }
+        if outdated > 0 {
+            bail!("{} installed package(s) can be updated.", outdated);
+        }
+
+        Ok(())
+    }
+
+    /// Compare an installed remote package against its remote repository.
+    fn outdated_entry<'r>(
+        &self,
+        id: &'r str,
+        receipt: &Receipt,
+        url: &str,
+    ) -> Result<OutdatedEntry<'r>> {
+        let refs = self.git.remote_refs(url)?;
+        let reference = receipt.reference.as_deref().unwrap_or("-");
+        let commit = receipt.commit.as_deref().unwrap_or("-");
+        let short = |c: &str| c.chars().take(7).collect::<String>();
+
+        let current = match receipt.ref_kind {
+            Some(RefKind::Branch) => [reference, "@", &short(commit)].concat(),
+            Some(RefKind::Commit) => short(commit),
+            _ => reference.to_owned(),
+        };
+        let wanted = match (&receipt.requirement, receipt.ref_kind) {
+            (Some(requirement), _) => {
+                let req = fmt::parse_version_req(requirement)?.unwrap_or_default();
+                git::highest_matching_tag(refs.keys(), &req)
+                    .unwrap_or("-")
+                    .to_owned()
+            }
+            (None, Some(RefKind::Branch)) => match refs.get(&["refs/heads/", reference].concat()) {
+                Some(head) => [reference, "@", &short(head)].concat(),
+                None => bail!("branch `{}` no longer exists on the remote", reference),
+            },
+            _ => current.clone(),
+        };
+        let latest = git::highest_matching_tag(refs.keys(), &semver::VersionReq::STAR);
+
+        // Fixed tags are also outdated when a newer one was released
+        let is_newer = match (
+            latest.and_then(git::tag_version),
+            git::tag_version(&current),
+        ) {
+            (Some(latest), Some(current)) => latest > current,
+            _ => false,
+        };
+
+        Ok(OutdatedEntry {
+            id,
+            outdated: wanted != current || is_newer,
+            current,
+            wanted,
+            latest: latest.map(|t| t.to_owned()),
+            error: None,
+        })
+    }
+
+    /// Command action to show the metadata and provided files of a package.
+    pub fn info(&self, pkg_name: &str) -> Result {
+        let pkg_fmt = PkgNameFmt::from(pkg_name)?;
+        let pkg_id = pkg_fmt.get_id();
+        let pkg_path = pkg_fmt.get_pkg_path();
+        let is_pkg_path = pkg_path.is_some();
+
+        // Check for a local package (directory path) or a remote one
+        let pkg_dir = match pkg_path {
+            Some(pkg_path) => pkg_path,
+            None => {
+                let pkg_name = &pkg_fmt.get_short_name();
+                if !self.paket.pkg_exists(pkg_name) {
+                    bail!(
+                        "package `{}` is not installed. Try to use the `add` command to install it first.",
+                        pkg_name
+                    );
+                }
+                self.git.base_dir.join(pkg_name)
+            }
+        };
+
+        let manifest =
+            self.paket
+                .read_pkg_dir_with_manifest(&pkg_dir, &pkg_fmt.pkg_name, is_pkg_path)?;
+        let toml_pkg = match manifest.package {
+            Some(toml_pkg) => toml_pkg,
+            None => bail!("`paket.toml` file could not be parsed correctly."),
         };
 
-        if !is_pkg_path {
-            // TODO: Prevent unnecessary clones for same versions (using cache)
-            // https://github.com/joseluisq/paket/issues/5
+        let receipt = self.receipts.get(&pkg_id);
+        let installed = if receipt.is_some() { "yes" } else { "no" };
+        let join = |v: &Option<Vec<String>>| v.as_ref().map(|v| v.join(", "));
+        let fields = [
+            ("Name", Some(toml_pkg.name.clone())),
+            ("Version", Some(toml_pkg.version.to_string())),
+            ("Description", toml_pkg.description.clone()),
+            ("Authors", join(&toml_pkg.authors)),
+            ("License", toml_pkg.license.clone()),
+            ("Repository", toml_pkg.repository.clone()),
+            ("Keywords", join(&toml_pkg.keywords)),
+            ("Categories", join(&toml_pkg.categories)),
+            ("Installed", Some(installed.to_owned())),
+            ("Provider", receipt.and_then(|r| r.provider.clone())),
+            ("Reference", receipt.and_then(receipt_reference)),
+            ("Commit", receipt.and_then(|r| r.commit.clone())),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                println!("{:<12} {}", format!("{}:", label), value);
+            }
+        }
+
+        // Classify the package files like the install process does
+        let files = self.paket.pkg_files(pkg_dir, &toml_pkg.include)?;
+        let sections = [
+            ("Functions", PkgFileKind::Function),
+            ("Completions", PkgFileKind::Completion),
+            ("Snippets (conf.d)", PkgFileKind::Snippet),
+            ("Included files", PkgFileKind::Included),
+        ];
+        for (label, kind) in sections {
+            let mut names = files
+                .iter()
+                .filter(|f| f.kind == kind)
+                .filter_map(|f| f.dest.file_name())
+                .map(|f| f.to_string_lossy())
+                .collect::<Vec<_>>();
+            if names.is_empty() {
+                continue;
+            }
+            names.sort();
+
+            println!("\n{}:", label);
+            for name in names {
+                println!("  {}", name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the cached mirror directories used by installed remote packages.
+    fn used_mirrors(&self) -> BTreeSet<PathBuf> {
+        self.receipts
+            .packages
+            .values()
+            .filter_map(|receipt| match &receipt.source {
+                ReceiptSource::Remote { url } => Some(self.cache.mirror_dir(url)),
+                ReceiptSource::Local { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Command action to list the cached Git repository mirrors.
+    pub fn cache_list(&self) -> Result {
+        let entries = self.cache.entries(&self.git)?;
+        if entries.is_empty() {
+            println!("No cached repositories.");
+            return Ok(());
+        }
+
+        let used = self.used_mirrors();
+        let rows = entries
+            .iter()
+            .map(|e| {
+                let is_used = if used.contains(&e.path) { "yes" } else { "no" };
+                vec![
+                    e.url.clone().unwrap_or("-".into()),
+                    helper_file::human_size(e.size),
+                    is_used.to_owned(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        table::print(&["URL", "SIZE", "USED"], &rows);
+
+        let total = entries.iter().map(|e| e.size).sum();
+        println!("\nTotal: {}", helper_file::human_size(total));
+
+        Ok(())
+    }
+
+    /// Command action to remove the cached Git repository mirrors
+    /// not used by installed packages or all of them.
+    pub fn cache_clean(&self, all: bool) -> Result {
+        let used = self.used_mirrors();
+        let mut removed = 0;
+        let mut freed = 0;
+        for entry in self.cache.entries(&self.git)? {
+            if !all && used.contains(&entry.path) {
+                continue;
+            }
+            self.cache.remove(&entry)?;
+            removed += 1;
+            freed += entry.size;
+        }
+
+        println!(
+            "Removed {} cached repository mirror(s) ({}).",
+            removed,
+            helper_file::human_size(freed)
+        );
+
+        Ok(())
+    }
+
+    /// Command action to converge the installed packages to a package list file
+    /// installing missing packages, updating the ones whose reference changed
+    /// and removing the ones no longer listed.
+    pub fn sync(
+        &mut self,
+        file: Option<PathBuf>,
+        dry_run: bool,
+        jobs: usize,
+        on_modified: OnModified,
+    ) -> Result {
+        if !dry_run {
+            self.record_missing_receipts()?;
+        }
+        let path = file.unwrap_or_else(|| TomlPackages::path(&self.paket.paths.paket_dir));
+        let list = TomlPackages::read(&path)?;
+
+        let mut listed = BTreeMap::new();
+        for spec in &list.packages {
+            let pkg_fmt = self
+                .parse_installed_pkg(spec)
+                .with_context(|| format!("package `{}` on package list is not valid.", spec))?;
+            let pkg_id = pkg_fmt.get_id();
+            if listed.insert(pkg_id.clone(), pkg_fmt).is_some() {
+                bail!("package `{}` is listed more than once.", pkg_id);
+            }
+        }
 
-            // NOTE: For now we just remove the "cached" Git repository package too and
-            // only for remote-installed ones
-            fs::remove_dir_all(pkg_dir)?;
+        // Plan which packages should be installed, updated or removed
+        let mut to_install = vec![];
+        let mut to_update = vec![];
+        for (pkg_id, pkg_fmt) in &listed {
+            match self.receipts.get(pkg_id) {
+                None => to_install.push(pkg_id.clone()),
+                Some(receipt) => {
+                    let is_remote = pkg_fmt.get_pkg_path().is_none();
+                    // Packages listed without a Git branch or tag keep their installed one
+                    let pkg_tag = pkg_fmt.pkg_tag.trim();
+                    let installed_ref = receipt
+                        .requirement
+                        .as_deref()
+                        .or(receipt.reference.as_deref())
+                        .unwrap_or_default();
+                    // Pinned commits can be listed by their abbreviated hash
+                    let is_same_ref = installed_ref == pkg_tag
+                        || (receipt.ref_kind == Some(RefKind::Commit)
+                            && fmt::is_commit_sha(pkg_tag)
+                            && installed_ref.starts_with(pkg_tag));
+                    if is_remote && !pkg_tag.is_empty() && !is_same_ref {
+                        to_update.push(pkg_id.clone());
+                    }
+                }
+            }
+        }
+
+        // Dependencies of listed packages are kept even if they are not listed.
+        // Linked packages are under development so they are kept along with their dependencies.
+        let kept_ids = self
+            .receipts
+            .packages
+            .iter()
+            .filter(|(_, r)| r.is_linked())
+            .map(|(id, _)| id.to_owned())
+            .chain(listed.keys().cloned())
+            .collect::<Vec<_>>();
+        let kept = self.receipts.dependency_closure(&kept_ids);
+        let to_remove = self
+            .receipts
+            .packages
+            .keys()
+            .filter(|pkg_id| !kept.contains(*pkg_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let to_remove = self.receipts.removal_order(to_remove);
+
+        if to_install.is_empty() && to_update.is_empty() && to_remove.is_empty() {
+            println!(
+                "Installed packages are already in sync with `{}`.",
+                path.display()
+            );
+            return Ok(());
         }
 
-        println!("Package was uninstalled successfully.");
+        println!("Sync plan for `{}`:", path.display());
+        for pkg_id in &to_install {
+            println!("  install  {}", pkg_id_with_tag(&listed[pkg_id]));
+        }
+        for pkg_id in &to_update {
+            let from = self
+                .receipts
+                .get(pkg_id)
+                .and_then(|r| r.requirement.as_deref().or(r.reference.as_deref()));
+            println!(
+                "  update   {} (from `{}`)",
+                pkg_id_with_tag(&listed[pkg_id]),
+                from.unwrap_or("-")
+            );
+        }
+        for pkg_id in &to_remove {
+            println!("  remove   {}", pkg_id);
+        }
+
+        if dry_run {
+            return Ok(());
+        }
+
+        for pkg_id in &to_remove {
+            let pkg_fmt = self.parse_installed_pkg(pkg_id)?;
+            // Dependents of the removed packages are removed first
+            self.transaction(|a| a.remove_pkg(&pkg_fmt, true, on_modified))?;
+        }
+
+        // Revert the unused package checkouts if something fails
+        let result = self.sync_pkgs(&listed, &to_install, &to_update, jobs, on_modified);
+        if result.is_err() {
+            self.discard_checkouts()?;
+        }
+        self.write_lockfile()?;
+        result?;
+
+        println!("Packages were synchronized successfully.");
         println!("Now just reload your current Fish shell session.");
 
         Ok(())
     }
+
+    /// Install and update the planned packages of a package list.
+    fn sync_pkgs(
+        &mut self,
+        listed: &BTreeMap<String, PkgNameFmt>,
+        to_install: &[String],
+        to_update: &[String],
+        jobs: usize,
+        on_modified: OnModified,
+    ) -> Result {
+        // Clone and fetch the packages concurrently (up to `jobs` at once) first
+        let git_provider = self.git.default_provider.clone();
+        let to_fetch = to_install
+            .iter()
+            .filter(|id| self.receipts.get(id).is_none());
+        let to_fetch = to_fetch.map(|id| &listed[id]).collect::<Vec<_>>();
+        let failed = self.prefetch(to_fetch, jobs, |a, f| a.checkout_install(f, &git_provider));
+        if self.paket.settings.offline() {
+            check_missing(&offline_failures(&failed))?;
+        }
+        if let Some((pkg_id, err)) = failed.into_iter().next() {
+            return Err(err.context(format!("package `{}` could not be installed.", pkg_id)));
+        }
+        let failed = self.prefetch(to_update.iter().map(|id| &listed[id]), jobs, |a, f| {
+            a.checkout_update(f)
+        });
+        if self.paket.settings.offline() {
+            check_missing(&offline_failures(&failed))?;
+        }
+        if let Some((pkg_id, err)) = failed.into_iter().next() {
+            return Err(err.context(format!("package `{}` could not be updated.", pkg_id)));
+        }
+
+        let to_install = to_install.iter().map(|id| &listed[id]).collect::<Vec<_>>();
+        for pkg_fmt in self.dependency_order(&to_install) {
+            // Skip packages already installed as dependencies of other listed ones
+            if self.receipts.get(&pkg_fmt.get_id()).is_some() {
+                continue;
+            }
+            self.transaction(|a| a.install_pkg(pkg_fmt, &git_provider, &mut vec![], false))?;
+        }
+        for pkg_id in to_update {
+            self.transaction(|a| a.update_pkg(&listed[pkg_id], on_modified, false))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the given package names dropping duplicates and failing on the same package
+/// given with different Git branches or tags.
+fn plan_pkgs<F>(pkg_names: &[String], parse: F) -> Result<Vec<PkgNameFmt>>
+where
+    F: Fn(&str) -> Result<PkgNameFmt>,
+{
+    let mut pkg_fmts: Vec<PkgNameFmt> = vec![];
+    for pkg_name in pkg_names {
+        let pkg_fmt = parse(pkg_name)?;
+        let pkg_id = pkg_fmt.get_id();
+        match pkg_fmts.iter().find(|f| f.get_id() == pkg_id) {
+            Some(other) if other.pkg_tag != pkg_fmt.pkg_tag => bail!(
+                "package `{}` was given more than once with different branches or tags.",
+                pkg_id
+            ),
+            Some(_) => continue,
+            None => pkg_fmts.push(pkg_fmt),
+        }
+    }
+    Ok(pkg_fmts)
+}
+
+/// Return the canonical path of a file or directory or its absolute path if it doesn't exist.
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Ok(path);
+    }
+    let path = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    Ok(path.components().collect())
+}
+
+/// Parse the path of a local package directory which must exist.
+fn local_pkg_fmt(pkg_dir: &Path) -> Result<PkgNameFmt> {
+    let pkg_fmt = PkgNameFmt::from(&pkg_dir.to_string_lossy())?;
+    if pkg_fmt.get_pkg_path().is_none() {
+        bail!(
+            "package directory \"{}\" doesn't exist or is not a directory.",
+            pkg_dir.display()
+        );
+    }
+    Ok(pkg_fmt)
+}
+
+/// Print the Git reference a remote package is about to be installed or updated from.
+fn print_checkout(action: &str, pkg_name: &str, origin: &PkgOrigin) {
+    if let Some(requirement) = &origin.requirement {
+        println!(
+            "Resolved version requirement `{}` to tag `{}`.",
+            requirement, origin.reference
+        );
+    }
+    println!("{} package `{}@{}`...", action, pkg_name, origin.reference);
+}
+
+/// Describe the package checkouts which failed in offline mode.
+fn offline_failures(failed: &BTreeMap<String, anyhow::Error>) -> Vec<String> {
+    failed
+        .iter()
+        .map(|(pkg_id, err)| format!("{}: {}", pkg_id, err))
+        .collect()
+}
+
+/// Fail listing the packages which are not available on the repository cache in offline mode.
+fn check_missing(missing: &[String]) -> Result {
+    if missing.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "{} package(s) are not available on the repository cache in offline mode:\n  {}",
+        missing.len(),
+        missing.join("\n  ")
+    )
+}
+
+/// Print a summary table of per-package results of a command action
+/// failing if some package failed.
+fn print_results(action: &str, results: &[(String, &str)]) -> Result {
+    let rows = results
+        .iter()
+        .map(|(pkg_id, status)| vec![pkg_id.to_owned(), status.to_string()])
+        .collect::<Vec<_>>();
+    println!();
+    table::print(&["PACKAGE", "STATUS"], &rows);
+
+    let failed = results.iter().filter(|(_, s)| *s == "failed").count();
+    if failed > 0 {
+        bail!(
+            "{} of {} package(s) could not be {}.",
+            failed,
+            results.len(),
+            action
+        );
+    }
+    if results.iter().any(|(_, s)| *s == action) {
+        println!("Now just reload your current Fish shell session.");
+    }
+
+    Ok(())
+}
+
+/// Check if an installation receipt records a given Git reference resolved to a given commit.
+fn is_installed_commit(receipt: Option<&Receipt>, reference: &str, commit: &str) -> bool {
+    receipt.is_some_and(|r| {
+        r.reference.as_deref() == Some(reference) && r.commit.as_deref() == Some(commit)
+    })
+}
+
+/// Return the Git reference of an installation receipt along with its kind (if known).
+fn receipt_reference(receipt: &Receipt) -> Option<String> {
+    let reference = receipt.reference.as_deref()?;
+    match (receipt.ref_kind, &receipt.requirement) {
+        (_, Some(requirement)) => Some(format!("{} (tag matching `{}`)", reference, requirement)),
+        (Some(RefKind::Branch), _) => Some(format!("{} (branch)", reference)),
+        (Some(RefKind::Tag), _) => Some(format!("{} (tag)", reference)),
+        (Some(RefKind::Commit), _) => Some(format!("{} (pinned commit)", reference)),
+        (None, _) => Some(reference.to_owned()),
+    }
+}
+
+/// Return the package identifier along with its requested Git branch or tag (if any).
+fn pkg_id_with_tag(pkg_fmt: &PkgNameFmt) -> String {
+    match pkg_fmt.pkg_tag.trim() {
+        "" => pkg_fmt.get_id(),
+        pkg_tag => [&pkg_fmt.get_id(), "@", pkg_tag].concat(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch_receipt(commit: &str) -> Receipt {
+        Receipt {
+            name: "gitnow".into(),
+            version: semver::Version::new(2, 5, 0),
+            source: ReceiptSource::Remote {
+                url: "https://github.com/joseluisq/gitnow.git".into(),
+            },
+            provider: Some("github".into()),
+            reference: Some("main".into()),
+            requirement: None,
+            ref_kind: Some(RefKind::Branch),
+            commit: Some(commit.into()),
+            dependencies: vec![],
+            files: vec![],
+        }
+    }
+
+    #[test]
+    fn unchanged_branch_is_up_to_date() {
+        let receipt = branch_receipt("43cbc41");
+        assert!(is_installed_commit(Some(&receipt), "main", "43cbc41"));
+    }
+
+    #[test]
+    fn moved_or_other_branch_is_not_up_to_date() {
+        let receipt = branch_receipt("43cbc41");
+        assert!(!is_installed_commit(Some(&receipt), "main", "bcbef2e"));
+        assert!(!is_installed_commit(Some(&receipt), "develop", "43cbc41"));
+        assert!(!is_installed_commit(None, "main", "43cbc41"));
+    }
 }
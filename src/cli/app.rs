@@ -1,4 +1,4 @@
-use crate::cli::{Actions, Commands};
+use crate::cli::{Actions, CacheCommands, Commands};
 use crate::paket::Paket;
 use crate::result::Result;
 
@@ -9,11 +9,60 @@ impl App {
         let mut actions = Actions::new(pk)?;
         if let Some(commands) = &pk.opts.commands {
             match commands {
-                Commands::Add { pkg_name, provider } => {
-                    actions.install(pkg_name.as_str(), provider.as_str())
+                Commands::Add {
+                    pkg_names,
+                    provider,
+                    jobs,
+                    force,
+                } => {
+                    let provider = provider
+                        .as_deref()
+                        .unwrap_or(pk.settings.default_provider());
+                    actions.install(
+                        pkg_names,
+                        provider,
+                        jobs.unwrap_or(pk.settings.jobs()),
+                        *force,
+                    )
                 }
-                Commands::Update { pkg_name } => actions.update(pkg_name.as_str()),
-                Commands::Remove { pkg_name } => actions.remove(pkg_name.as_str()),
+                Commands::Install { locked } => actions.install_from_lockfile(*locked),
+                Commands::Update {
+                    pkg_names,
+                    jobs,
+                    on_modified,
+                    force,
+                } => actions.update(
+                    pkg_names,
+                    jobs.unwrap_or(pk.settings.jobs()),
+                    *on_modified,
+                    *force,
+                ),
+                Commands::Remove {
+                    pkg_names,
+                    force,
+                    on_modified,
+                } => actions.remove(pkg_names, *force, *on_modified),
+                Commands::Sync {
+                    file,
+                    dry_run,
+                    jobs,
+                    on_modified,
+                } => actions.sync(
+                    file.clone(),
+                    *dry_run,
+                    jobs.unwrap_or(pk.settings.jobs()),
+                    *on_modified,
+                ),
+                Commands::List { json } => actions.list(*json),
+                Commands::Outdated { json } => actions.outdated(*json),
+                Commands::Info { pkg_name } => actions.info(pkg_name.as_str()),
+                Commands::Link { pkg_dir, force } => actions.link(pkg_dir, *force),
+                Commands::Unlink { pkg_dir } => actions.unlink(pkg_dir),
+                Commands::Dev { pkg_dir, force } => actions.dev(pkg_dir, *force),
+                Commands::Cache { command } => match command {
+                    CacheCommands::List => actions.cache_list(),
+                    CacheCommands::Clean { all } => actions.cache_clean(*all),
+                },
             }?
         }
 
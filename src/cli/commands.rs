@@ -1,36 +1,175 @@
 use clap::Parser;
+use std::path::PathBuf;
 
 /// A simple and fast package manager for the Fish shell 📦
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct CommandOpts {
+    #[arg(long, global = true)]
+    /// Install packages only from the repository cache without accessing the network.
+    pub offline: bool,
+
     #[command(subcommand)]
     pub commands: Option<Commands>,
 }
 
 #[derive(clap::Subcommand, Debug)]
 pub enum Commands {
-    /// Install a new package from a local or remote repository.
+    /// Install new packages from local or remote repositories.
     #[command(name = "add")]
     Add {
-        #[arg(short = 'p', long, default_value = "github")]
-        /// A Git host provider like github, bitbucket or gitlab.
-        provider: String,
-        /// Package name. E.g joseluisq/gitnow
-        pkg_name: String,
+        #[arg(short = 'p', long)]
+        /// A Git host provider like github (default), bitbucket or gitlab.
+        provider: Option<String>,
+        #[arg(short = 'j', long)]
+        /// Maximum number of concurrent Git operations. Defaults to `4`.
+        jobs: Option<usize>,
+        #[arg(short = 'f', long)]
+        /// Replace files provided by other packages or by the user keeping them to restore them later.
+        force: bool,
+        /// Package names. E.g joseluisq/gitnow
+        #[arg(required = true)]
+        pkg_names: Vec<String>,
+    },
+
+    /// Install all packages recorded in the `paket.lock` file.
+    #[command(name = "install")]
+    Install {
+        #[arg(long)]
+        /// Install the exact locked commits failing if they don't match.
+        locked: bool,
     },
 
-    /// Update an existing package from a local or remote repository.
+    /// Update existing packages from local or remote repositories or all installed ones if none.
     #[command(name = "up")]
     Update {
-        /// Package name. E.g joseluisq/gitnow
+        #[arg(short = 'j', long)]
+        /// Maximum number of concurrent Git operations. Defaults to `4`.
+        jobs: Option<usize>,
+        #[arg(long, value_enum, default_value_t)]
+        /// What to do with installed package files modified locally.
+        on_modified: OnModified,
+        #[arg(short = 'f', long)]
+        /// Replace files provided by other packages or by the user keeping them to restore them later.
+        force: bool,
+        /// Package names. E.g joseluisq/gitnow
+        pkg_names: Vec<String>,
+    },
+
+    /// Install, update or remove packages to match a package list file.
+    #[command(name = "sync")]
+    Sync {
+        #[arg(short = 'f', long)]
+        /// Package list file path. Defaults to `~/.config/paket/packages.toml`.
+        file: Option<PathBuf>,
+        #[arg(long)]
+        /// Print the sync plan without applying it.
+        dry_run: bool,
+        #[arg(short = 'j', long)]
+        /// Maximum number of concurrent Git operations. Defaults to `4`.
+        jobs: Option<usize>,
+        #[arg(long, value_enum, default_value_t)]
+        /// What to do with installed package files modified locally.
+        on_modified: OnModified,
+    },
+
+    /// List all installed packages.
+    #[command(name = "list")]
+    List {
+        #[arg(long)]
+        /// Print the installed packages in JSON format.
+        json: bool,
+    },
+
+    /// Report installed packages which can be updated.
+    #[command(name = "outdated")]
+    Outdated {
+        #[arg(long)]
+        /// Print the outdated packages report in JSON format.
+        json: bool,
+    },
+
+    /// Show metadata and provided files of an installed or local package.
+    #[command(name = "info")]
+    Info {
+        /// Package name or local package directory path. E.g joseluisq/gitnow
         pkg_name: String,
     },
 
-    /// Uninstall an existing package from a local or remote repository.
+    /// Uninstall existing packages from local or remote repositories.
     #[command(name = "rm")]
     Remove {
-        /// Package name. E.g joseluisq/gitnow
-        pkg_name: String,
+        #[arg(short = 'f', long)]
+        /// Remove the packages even if other installed packages depend on them.
+        force: bool,
+        #[arg(long, value_enum, default_value_t)]
+        /// What to do with installed package files modified locally.
+        on_modified: OnModified,
+        /// Package names. E.g joseluisq/gitnow
+        #[arg(required = true)]
+        pkg_names: Vec<String>,
+    },
+
+    /// Symlink the files of a local package into Fish shell directories for development.
+    #[command(name = "link")]
+    Link {
+        #[arg(short = 'f', long)]
+        /// Replace files provided by other packages or by the user keeping them to restore them later.
+        force: bool,
+        /// Local package directory path. E.g ~/dev/my-plugin
+        pkg_dir: PathBuf,
+    },
+
+    /// Remove the symbolic links of a linked local package.
+    #[command(name = "unlink")]
+    Unlink {
+        /// Local package directory path. E.g ~/dev/my-plugin
+        pkg_dir: PathBuf,
+    },
+
+    /// Link a local package and keep it in sync with the changes of its directory.
+    #[command(name = "dev")]
+    Dev {
+        #[arg(short = 'f', long)]
+        /// Replace files provided by other packages or by the user keeping them to restore them later.
+        force: bool,
+        /// Local package directory path. E.g ~/dev/my-plugin
+        pkg_dir: PathBuf,
+    },
+
+    /// Inspect or prune the cache of Git repository mirrors.
+    #[command(name = "cache")]
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+}
+
+/// Policy applied to installed package files modified locally when they are
+/// going to be replaced or removed.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnModified {
+    /// Leave the modified files untouched.
+    Skip,
+    /// Keep a copy of the modified files (`*.paket-backup`) before replacing or removing them.
+    #[default]
+    Backup,
+    /// Stop without changing anything.
+    Abort,
+}
+
+/// Subcommands of the `cache` command.
+#[derive(clap::Subcommand, Debug)]
+pub enum CacheCommands {
+    /// List the cached Git repository mirrors.
+    #[command(name = "list")]
+    List,
+
+    /// Remove the cached Git repository mirrors not used by installed packages.
+    #[command(name = "clean")]
+    Clean {
+        #[arg(long)]
+        /// Remove all cached mirrors including the ones used by installed packages.
+        all: bool,
     },
 }
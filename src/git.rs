@@ -1,8 +1,26 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::helpers::Command;
+use crate::provider::Providers;
 use crate::result::Result;
+use crate::settings::Settings;
+
+/// Git host provider used when no one is specified.
+pub const DEFAULT_PROVIDER: &str = "github";
+
+/// Kind of a Git reference a package is installed from.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefKind {
+    /// A Git branch which can move forward.
+    Branch,
+    /// A fixed Git tag.
+    Tag,
+    /// An exact Git commit the package is pinned to.
+    Commit,
+}
 
 /// Git commands set interface.
 pub struct Git {
@@ -10,10 +28,16 @@ pub struct Git {
     pub base_dir: PathBuf,
     /// Current directory when a command is performed.
     pub current_dir: PathBuf,
+    /// Git host provider used when no one is specified.
+    pub default_provider: String,
+    /// Git branch or tag used when no one is specified (if configured).
+    pub default_ref: Option<String>,
+    /// Registry of Git host providers.
+    pub providers: Providers,
 }
 
 impl Git {
-    pub fn new<P: AsRef<Path>>(base_dir: P) -> Result<Self>
+    pub fn new<P: AsRef<Path>>(base_dir: P, settings: &Settings) -> Result<Self>
     where
         PathBuf: From<P>,
     {
@@ -21,67 +45,122 @@ impl Git {
         Ok(Self {
             base_dir: base_dir.to_owned(),
             current_dir: base_dir,
+            default_provider: settings.default_provider().to_owned(),
+            default_ref: settings.default_ref().map(|r| r.to_owned()),
+            providers: Providers::new(&settings.providers)?,
         })
     }
 
-    fn get_remote_endpoint(user_repo_name: &str, provider: &str) -> Result<String> {
-        let provider = match provider {
-            "github" => "github.com",
-            "bitbucket" => "bitbucket.org",
-            "gitlab" => "gitlab.com",
-            _ => bail!(
-                "git host provider not supported. use a full url endpoint with `add` command instead"
+    /// Return the remote repository URL of a package on a given Git host provider.
+    pub fn get_remote_endpoint(&self, user_repo_name: &str, provider: &str) -> Result<String> {
+        match self.providers.endpoint(provider, user_repo_name) {
+            Some(endpoint) => Ok(endpoint),
+            None => bail!(
+                "git host provider `{}` not supported. define it on the `[providers]` section of the paket configuration file or use a full git url with `add` command instead",
+                provider
             ),
-        };
+        }
+    }
 
-        Ok(["https://", provider, "/", user_repo_name, ".git"].concat())
+    /// Return the Git host provider name and the `username/repository` name of a remote URL.
+    pub fn get_provider_from_url(&self, url: &str) -> Result<(String, String)> {
+        match self.providers.find_by_url(url) {
+            Some(found) => Ok(found),
+            None => bail!("git url `{}` doesn't match any git host provider", url),
+        }
     }
 
     fn exec_name(&self) -> &'static str {
         "git"
     }
 
-    /// Clone a Git repository.
-    pub fn clone(
-        &self,
-        user_repo_name: &str,
-        branch_tag: Option<&str>,
-        git_provider: &str,
-    ) -> Result<String> {
-        let endpoint = Git::get_remote_endpoint(user_repo_name, git_provider)?;
-
-        let out_dir = self.base_dir.join(user_repo_name);
-        if !out_dir.exists() {
-            fs::create_dir_all(&out_dir)?;
+    /// Create the bare mirror of a remote repository or refresh it if it already exists.
+    pub fn mirror(&self, mirror_dir: &Path, endpoint: &str) -> Result {
+        if mirror_dir.is_dir() {
+            Command::new(self.exec_name(), Some(&mirror_dir.to_path_buf()))
+                .arg("fetch")
+                .arg("--quiet")
+                .arg("--prune")
+                .arg("origin")
+                .execute()?;
+            return Ok(());
         }
 
-        let branch_tag = branch_tag.unwrap_or("master");
-        let branch_str = ["--branch=", branch_tag].concat();
-
+        if let Some(parent) = mirror_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
         Command::new(self.exec_name(), Some(&self.current_dir))
             .arg("clone")
-            .arg("--depth=1")
-            .arg(branch_str)
-            .arg(&endpoint)
-            .arg(out_dir)
+            .arg("--quiet")
+            .arg("--mirror")
+            .arg(endpoint)
+            .arg(mirror_dir)
+            .execute()?;
+
+        Ok(())
+    }
+
+    /// Resolve a Git branch, tag or full or abbreviated commit hash of a repository
+    /// into a full commit hash.
+    pub fn resolve_commit(&self, repo_dir: &Path, reference: &str) -> Result<String> {
+        let out = Command::new(self.exec_name(), Some(&repo_dir.to_path_buf()))
+            .arg("rev-parse")
+            .arg("--verify")
+            .arg("--quiet")
+            .arg([reference, "^{commit}"].concat())
             .execute()
+            .map_err(|_| anyhow!("commit of `{}` was not found", reference))?;
+        Ok(out.trim().to_owned())
     }
 
-    /// Fetch a Git branch or tag.
-    pub fn fetch(&mut self, user_repo_name: &str, branch_tag: Option<&str>) -> Result<String> {
-        let branch_tag = branch_tag.unwrap_or("master");
-        let cwd = self.base_dir.join(user_repo_name).canonicalize()?;
+    /// Check out an exact commit of a package in detached mode materializing it from a local
+    /// mirror. The package repository is cloned from the mirror if it doesn't exist yet
+    /// and its `origin` remote points to the given remote repository URL.
+    pub fn checkout_from_mirror(
+        &self,
+        user_repo_name: &str,
+        mirror_dir: &Path,
+        endpoint: &str,
+        commit: &str,
+    ) -> Result {
+        let out_dir = self.base_dir.join(user_repo_name);
+        if out_dir.join(".git").exists() {
+            Command::new(self.exec_name(), Some(&out_dir))
+                .arg("fetch")
+                .arg("--quiet")
+                .arg("--tags")
+                .arg(mirror_dir)
+                .arg("+refs/heads/*:refs/remotes/origin/*")
+                .execute()?;
+        } else {
+            fs::create_dir_all(&out_dir)?;
+            Command::new(self.exec_name(), Some(&self.current_dir))
+                .arg("clone")
+                .arg("--quiet")
+                .arg("--no-checkout")
+                .arg(mirror_dir)
+                .arg(&out_dir)
+                .execute()?;
+            Command::new(self.exec_name(), Some(&out_dir))
+                .arg("remote")
+                .arg("set-url")
+                .arg("origin")
+                .arg(endpoint)
+                .execute()?;
+        }
 
-        Command::new(self.exec_name(), Some(&cwd))
-            .arg("fetch")
-            .arg("--depth=1")
-            .arg("origin")
-            .arg(branch_tag)
-            .execute()
+        Command::new(self.exec_name(), Some(&out_dir))
+            .arg("checkout")
+            .arg("--quiet")
+            .arg("--detach")
+            .arg(commit)
+            .execute()?;
+
+        Ok(())
     }
 
     /// Checkout to an specific Git branch or tag.
-    pub fn checkout(&mut self, user_repo_name: &str, branch: Option<&str>) -> Result<String> {
+    pub fn checkout(&self, user_repo_name: &str, branch: Option<&str>) -> Result<String> {
         if branch.is_none() {
             bail!("provide a branch to switch to.");
         }
@@ -95,22 +174,111 @@ impl Git {
             .execute()
     }
 
-    /// Pull Git repository changes.
-    pub fn pull(&mut self, user_repo_name: &str) -> Result<String> {
-        let repo_dir = self.base_dir.join(user_repo_name);
-        if !repo_dir.exists() {
-            bail!("repository `{}` was not found", user_repo_name);
+    /// Return the default branch of a remote repository resolving its `HEAD` symbolic reference.
+    pub fn remote_default_branch(&self, endpoint: &str) -> Result<String> {
+        let out = Command::new(self.exec_name(), Some(&self.current_dir))
+            .arg("ls-remote")
+            .arg("--symref")
+            .arg(endpoint)
+            .arg("HEAD")
+            .execute()?;
+
+        // E.g `ref: refs/heads/main	HEAD`
+        let branch = out.lines().find_map(|line| {
+            line.strip_prefix("ref:")?
+                .split_whitespace()
+                .next()?
+                .strip_prefix("refs/heads/")
+        });
+        match branch {
+            Some(branch) => Ok(branch.to_owned()),
+            None => bail!("remote repository `{}` has no default branch", endpoint),
         }
+    }
 
-        Command::new(self.exec_name(), Some(&repo_dir))
-            .arg("pull")
-            .arg("origin")
-            .arg("master")
-            .execute()
+    /// Return the kind of a Git branch or tag on a remote repository.
+    pub fn remote_ref_kind(&self, endpoint: &str, reference: &str) -> Result<RefKind> {
+        let out = Command::new(self.exec_name(), Some(&self.current_dir))
+            .arg("ls-remote")
+            .arg("--heads")
+            .arg("--tags")
+            .arg(endpoint)
+            .arg(reference)
+            .execute()?;
+
+        let refs = out
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .collect::<Vec<_>>();
+        if refs.contains(&["refs/heads/", reference].concat().as_str()) {
+            Ok(RefKind::Branch)
+        } else if refs.contains(&["refs/tags/", reference].concat().as_str()) {
+            Ok(RefKind::Tag)
+        } else {
+            bail!(
+                "branch or tag `{}` was not found on remote repository `{}`",
+                reference,
+                endpoint
+            )
+        }
+    }
+
+    /// Return the highest semver tag of a remote repository matching a version requirement.
+    /// Tags can be prefixed with a `v` (e.g `v2.4.0`).
+    pub fn latest_matching_tag(&self, endpoint: &str, req: &semver::VersionReq) -> Result<String> {
+        let refs = self.remote_refs(endpoint)?;
+        match highest_matching_tag(refs.keys(), req) {
+            Some(tag) => Ok(tag.to_owned()),
+            None => bail!(
+                "no tag matching `{}` was found on remote repository `{}`",
+                req,
+                endpoint
+            ),
+        }
+    }
+
+    /// Return all branch and tag references of a remote repository mapped to their commit hashes.
+    /// E.g `refs/heads/main` or `refs/tags/2.4.0`.
+    pub fn remote_refs(&self, endpoint: &str) -> Result<BTreeMap<String, String>> {
+        let out = Command::new(self.exec_name(), Some(&self.current_dir))
+            .arg("ls-remote")
+            .arg("--heads")
+            .arg("--tags")
+            .arg("--refs")
+            .arg(endpoint)
+            .execute()?;
+
+        let refs = out
+            .lines()
+            .filter_map(|line| {
+                let (commit, reference) = line.split_once(char::is_whitespace)?;
+                Some((reference.trim().to_owned(), commit.trim().to_owned()))
+            })
+            .collect();
+        Ok(refs)
+    }
+
+    /// Return the commit hash that `HEAD` points to on a given repository directory.
+    pub fn head_commit(&self, repo_dir: &Path) -> Result<String> {
+        let out = Command::new(self.exec_name(), Some(&repo_dir.to_path_buf()))
+            .arg("rev-parse")
+            .arg("HEAD")
+            .execute()?;
+        Ok(out.trim().to_owned())
+    }
+
+    /// Return the `origin` remote URL of a given repository directory.
+    pub fn remote_url(&self, repo_dir: &Path) -> Result<String> {
+        let out = Command::new(self.exec_name(), Some(&repo_dir.to_path_buf()))
+            .arg("config")
+            .arg("--get")
+            .arg("remote.origin.url")
+            .execute()?;
+        Ok(out.trim().to_owned())
     }
 
     /// Check if given directory is a valid Git repository returning `Ok` if so or an error otherwise.
-    pub fn check_valid_repo(&mut self, repo_dir: &Path) -> Result<()> {
+    pub fn check_valid_repo(&self, repo_dir: &Path) -> Result<()> {
         match Command::new(self.exec_name(), Some(&repo_dir.to_path_buf()))
             .arg("rev-parse")
             .arg("--is-inside-work-tree")
@@ -129,3 +297,21 @@ impl Git {
         }
     }
 }
+
+/// Parse the semver version of a Git tag tolerating a `v` prefix (e.g `v2.4.0`).
+pub fn tag_version(tag: &str) -> Option<semver::Version> {
+    semver::Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// Return the highest semver tag of a set of Git references matching a version requirement.
+pub fn highest_matching_tag<'a, I>(refs: I, req: &semver::VersionReq) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    refs.into_iter()
+        .filter_map(|reference| reference.strip_prefix("refs/tags/"))
+        .filter_map(|tag| Some((tag_version(tag)?, tag)))
+        .filter(|(version, _)| req.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
@@ -3,6 +3,8 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use sha2::{Digest, Sha256};
+
 use crate::result::{Context, Result};
 
 /// Check if a path matches a given list of suffixes.
@@ -14,6 +16,16 @@ where
     suffixes.iter().any(|x| path.ends_with(x))
 }
 
+/// Expand a leading `~` of a path string into the user's home directory.
+pub fn expand_tilde(path: &str) -> String {
+    if path == "~" || path.starts_with("~/") {
+        if let Some(home_dir) = dirs::home_dir() {
+            return [home_dir.to_string_lossy().as_ref(), &path[1..]].concat();
+        }
+    }
+    path.to_owned()
+}
+
 /// Read an UTF-8 file from a specific path.
 pub fn read(path: &Path) -> Result<String> {
     match String::from_utf8(read_bytes(path)?) {
@@ -27,6 +39,57 @@ pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
     fs::read(path).with_context(|| format!("failed to read `{}`", path.display()))
 }
 
+/// Write a file atomically writing a temporary file next to it first
+/// and then moving it into place.
+pub fn write_atomic(path: &Path, data: &str) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)
+}
+
+/// Compute the SHA-256 checksum of a file returning it as a lowercase hex string.
+pub fn checksum(path: &Path) -> Result<String> {
+    Ok(checksum_bytes(&read_bytes(path)?))
+}
+
+/// Compute the SHA-256 checksum of a bytes slice returning it as a lowercase hex string.
+pub fn checksum_bytes(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Return the total size in bytes of all files inside a directory recursively.
+pub fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        size += if meta.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            meta.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Format a size in bytes using binary units. E.g `1.5 MiB`.
+pub fn human_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
 pub fn stringify(dst: &mut String, path: &serde_ignored::Path<'_>) {
     use serde_ignored::Path;
 
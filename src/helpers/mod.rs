@@ -1,5 +1,7 @@
 pub mod cmd;
 pub mod file;
+pub mod pool;
 pub mod process;
+pub mod table;
 
 pub use cmd::*;
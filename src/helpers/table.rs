@@ -0,0 +1,21 @@
+/// Print a plain text table with left-aligned columns separated by two spaces.
+pub fn print(header: &[&str], rows: &[Vec<String>]) {
+    let header = header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+
+    let mut widths = vec![0; header.len()];
+    for row in std::iter::once(&header).chain(rows) {
+        for (width, col) in widths.iter_mut().zip(row) {
+            *width = (*width).max(col.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(col, width)| format!("{:width$}", col, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
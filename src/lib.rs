@@ -10,11 +10,16 @@ extern crate serde;
 #[macro_use]
 pub mod result;
 
+pub mod cache;
 pub mod cli;
 pub mod git;
 pub mod helpers;
 pub mod paket;
 pub mod pkg;
+pub mod provider;
+pub mod settings;
+pub mod transaction;
+pub mod watch;
 
 pub use crate::paket::*;
 pub use crate::result::*;
@@ -8,6 +8,7 @@ use crate::cli::{App, CommandOpts};
 use crate::helpers::{file as helper_file, process, Command};
 use crate::pkg::config;
 use crate::result::{Context, Result};
+use crate::settings::Settings;
 
 /// Packet events supported transferable to Fish shell events.
 pub enum PaketEvents {
@@ -16,6 +17,30 @@ pub enum PaketEvents {
     BeforeUninstall,
 }
 
+/// Kind of file a package provides to the Fish shell directories.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PkgFileKind {
+    /// Fish configuration snippet (`conf.d/*.fish`).
+    Snippet,
+    /// Fish completion (`completions/*.fish`).
+    Completion,
+    /// Fish function (`functions/*.fish`).
+    Function,
+    /// Non Fish file listed on the `include` toml array.
+    Included,
+}
+
+/// A package file along with its destination on the Fish shell directories.
+#[derive(Clone, Debug)]
+pub struct PkgFile {
+    /// Source file path inside the package directory.
+    pub src: PathBuf,
+    /// Destination file path inside the Fish shell directories.
+    pub dest: PathBuf,
+    /// Kind of the package file.
+    pub kind: PkgFileKind,
+}
+
 /// Defines directory paths used by `Paket`.
 pub struct PaketPaths {
     /// User configuration directory.
@@ -30,6 +55,8 @@ pub struct PaketPaths {
     pub fish_functions_dir: PathBuf,
     /// Paket config directory.
     pub paket_dir: PathBuf,
+    /// Paket cache directory.
+    pub cache_dir: PathBuf,
 }
 
 /// Paket is a package manager for the Fish shell.
@@ -38,6 +65,8 @@ pub struct Paket {
     pub paths: PaketPaths,
     /// Contain `Paket` command options.
     pub opts: CommandOpts,
+    /// Contain the Paket configuration file settings.
+    pub settings: Settings,
 }
 
 impl<'a> Paket {
@@ -63,14 +92,22 @@ impl<'a> Paket {
             bail!("Paket is not running on top of a Fish shell session. Just run `fish` and then use `paket` from there.")
         }
 
-        let paths = Self::configure_paths()?;
+        let mut settings = Settings::load()?;
+        let paths = Self::configure_paths(&settings)?;
         let opts = CommandOpts::parse();
+        if opts.offline {
+            settings.offline = Some(true);
+        }
 
-        Ok(Self { paths, opts })
+        Ok(Self {
+            paths,
+            opts,
+            settings,
+        })
     }
 
     /// Configure directory paths used by `Paket`.
-    fn configure_paths() -> Result<PaketPaths> {
+    fn configure_paths(settings: &Settings) -> Result<PaketPaths> {
         // User's home directory
         let home_dir = dirs::home_dir()
             .expect("User home directory was not found or inaccessible.")
@@ -87,8 +124,11 @@ impl<'a> Paket {
             .with_context(|| "Home config directory was not found or inaccessible.")?;
 
         // Fish config directories
-        let fish_dir = config_dir
-            .join("fish")
+        let fish_dir = settings
+            .paths
+            .fish_dir
+            .clone()
+            .unwrap_or_else(|| config_dir.join("fish"))
             .canonicalize()
             .with_context(|| "Fish config directory was not found or inaccessible.")?;
 
@@ -114,7 +154,11 @@ impl<'a> Paket {
         }
 
         // Paket config directory
-        let paket_dir = config_dir.join("paket");
+        let paket_dir = settings
+            .paths
+            .paket_dir
+            .clone()
+            .unwrap_or_else(|| config_dir.join("paket"));
         if !paket_dir.exists() {
             fs::create_dir_all(&paket_dir)
                 .with_context(|| "Paket config directory can not be created.")?;
@@ -123,6 +167,13 @@ impl<'a> Paket {
             .canonicalize()
             .with_context(|| "Paket config directory was not found or inaccessible.")?;
 
+        // Paket cache directory (created on demand)
+        let cache_dir = settings.paths.cache_dir.clone().unwrap_or_else(|| {
+            dirs::cache_dir()
+                .unwrap_or_else(|| home_dir.join(".cache"))
+                .join("paket")
+        });
+
         Ok(PaketPaths {
             config_dir,
             fish_dir,
@@ -130,6 +181,7 @@ impl<'a> Paket {
             fish_completions_dir,
             fish_functions_dir,
             paket_dir,
+            cache_dir,
         })
     }
 
@@ -200,6 +252,21 @@ impl<'a> Paket {
     where
         F: FnMut(&PathBuf, &PathBuf) -> Result,
     {
+        for file in self.pkg_files(pkg_dir, pkg_include)? {
+            func(&file.src, &file.dest)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a given package directory with its extra include directories and
+    /// return every package file classified by kind along with its equivalent
+    /// destination file path.
+    pub fn pkg_files(
+        &'a self,
+        pkg_dir: PathBuf,
+        pkg_include: &Option<Vec<String>>,
+    ) -> Result<Vec<PkgFile>> {
         // Read `include` toml property of `package` section
         let pkg_include = &pkg_include.clone().unwrap_or_default();
 
@@ -213,6 +280,7 @@ impl<'a> Paket {
         let completions_dir = &self.paths.fish_completions_dir;
         let functions_dir = &self.paths.fish_functions_dir;
 
+        let mut files = vec![];
         let mut stack_paths = vec![pkg_dir];
         let path_suffixes = vec!["conf.d", "completions", "functions"];
 
@@ -236,14 +304,17 @@ impl<'a> Paket {
                     }
 
                     let mut fish_dir = snippets_dir;
+                    let mut kind = PkgFileKind::Snippet;
                     if parent.ends_with("completions") {
                         fish_dir = completions_dir;
+                        kind = PkgFileKind::Completion;
                     }
                     if parent.ends_with("functions") {
                         fish_dir = functions_dir;
+                        kind = PkgFileKind::Function;
                     }
 
-                    // call callback function with a source and destination paths
+                    // collect the source and destination paths
                     match path.file_name() {
                         Some(filename) => {
                             let filename = filename.to_str();
@@ -252,11 +323,15 @@ impl<'a> Paket {
                                 _ => false,
                             };
 
-                            let dest_path = fish_dir.join(filename.unwrap());
+                            let dest = fish_dir.join(filename.unwrap());
 
                             // Copy Fish shell files
                             if is_fish_file {
-                                func(&path, &dest_path)?;
+                                files.push(PkgFile {
+                                    src: path,
+                                    dest,
+                                    kind,
+                                });
                                 continue;
                             }
 
@@ -266,7 +341,11 @@ impl<'a> Paket {
                             }
                             let is_included = pkg_include.iter().any(|f| path.ends_with(f));
                             if is_included {
-                                func(&path, &dest_path)?;
+                                files.push(PkgFile {
+                                    src: path,
+                                    dest,
+                                    kind: PkgFileKind::Included,
+                                });
                             }
                         }
                         None => {
@@ -277,7 +356,7 @@ impl<'a> Paket {
             }
         }
 
-        Ok(())
+        Ok(files)
     }
 
     /// Process a Paket event definition, validating it with manifest file and
@@ -287,6 +366,26 @@ impl<'a> Paket {
         pkgname: &str,
         events: &config::TomlEvents,
         event_type: PaketEvents,
+    ) -> Result {
+        if !self.settings.events_enabled() {
+            return Ok(());
+        }
+
+        match self.dispatch_event(pkgname, events, event_type) {
+            Err(err) if !self.settings.events_fatal() => {
+                println!("Warning: Fish shell event could not be emitted: {:#}", err);
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    /// Dispatch the Fish shell event corresponding to a Paket event definition.
+    fn dispatch_event(
+        &self,
+        pkgname: &str,
+        events: &config::TomlEvents,
+        event_type: PaketEvents,
     ) -> Result {
         let pkg_event_val = match event_type {
             PaketEvents::AfterInstall => &events.after_install,
@@ -1,3 +1,4 @@
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
@@ -48,10 +49,14 @@ pub struct TomlPackage {
 
     // Events
     pub events: Option<TomlEvents>,
-    // TODO: Dependencies
 }
 
 pub fn read_pkg_file(path: &Path) -> Result<TomlManifest> {
+    read_toml_with_warnings(path, "configuration manifest")
+}
+
+/// Read and deserialize a TOML file printing a warning for every unused or unsupported key.
+pub fn read_toml_with_warnings<T: DeserializeOwned>(path: &Path, kind: &str) -> Result<T> {
     // Validate TOML file extension
     let ext = path.extension();
     if ext.is_none() || ext.unwrap().is_empty() || ext.unwrap().ne("toml") {
@@ -61,7 +66,7 @@ pub fn read_pkg_file(path: &Path) -> Result<TomlManifest> {
     // TODO: validate minimal TOML file structure needed
     let toml = read_toml_file(path).with_context(|| "error reading toml configuration file")?;
     let mut unused = BTreeSet::new();
-    let manifest: TomlManifest = serde_ignored::deserialize(toml, |path| {
+    let value: T = serde_ignored::deserialize(toml, |path| {
         let mut key = String::new();
         file::stringify(&mut key, &path);
         unused.insert(key);
@@ -69,10 +74,10 @@ pub fn read_pkg_file(path: &Path) -> Result<TomlManifest> {
     .with_context(|| "error during toml configuration file deserialization")?;
 
     for key in unused {
-        println!("Warning: unused configuration manifest key \"{key}\" or unsupported");
+        println!("Warning: unused {kind} key \"{key}\" or unsupported");
     }
 
-    Ok(manifest)
+    Ok(value)
 }
 
 /// Read and parse a TOML file from an specific path.
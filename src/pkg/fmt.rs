@@ -1,5 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use semver::VersionReq;
+
+use crate::git::Git;
+use crate::pkg::config::TomlDependency;
 use crate::result::{Context, Result};
 
 /// Defaines the package name format based on a fomatted package name string.
@@ -10,20 +14,23 @@ pub struct PkgNameFmt {
     pub pkg_name: String,
     /// Contain the package version name (Git branch or tag).
     pub pkg_tag: String,
+    /// Contain an exact Git commit to install (if any).
+    pub pkg_commit: Option<String>,
 
     pkg_path: Option<PathBuf>,
+    pkg_url: Option<String>,
 }
 
 impl PkgNameFmt {
     /// Return a `PkgNameFmt` instance but making sure that current package name format is valid.
-    /// Format: username/package_name@(tag_name|branch_name)
+    /// Format: username/package_name@(tag_name|branch_name|version_requirement|latest|commit)
     pub fn from(pkg_name: &str) -> Result<Self> {
         if pkg_name.is_empty() {
             bail!("provide a package name or a local Git package directory path.");
         }
 
-        // Default Git tag for package repository
-        let mut pkg_tag = "master";
+        // Git branch or tag for package repository (empty means the default one)
+        let mut pkg_tag = "";
 
         // Check if current `pkg_name` is an Git based package path directory
         let pkg_path = std::path::Path::new(pkg_name);
@@ -31,64 +38,433 @@ impl PkgNameFmt {
             let pkg_path = pkg_path
                 .canonicalize()
                 .with_context(|| "Package path directory doesn't exist or inaccessible.")?;
+            return Self::from_local_path(pkg_path);
+        }
 
-            // We take the dirname as package name
-            let pkg_name = match pkg_path.iter().next_back() {
-                Some(v) => v.to_str().unwrap().into(),
-                None => bail!(
-                    "directory name for path \"{}\" was not determined",
-                    pkg_path.display(),
-                ),
-            };
-
-            return Ok(Self {
-                user_name: String::new(),
-                pkg_name,
-                pkg_tag: pkg_tag.into(),
-                pkg_path: Some(pkg_path),
-            });
+        // Check if current `pkg_name` is a Git repository URL
+        if is_git_url(pkg_name) {
+            return Self::from_git_url(pkg_name);
         }
 
-        let pkg_parts: Vec<&str> = pkg_name.splitn(2, '/').collect();
-        if pkg_parts.len() < 2 {
-            bail!(
+        // The Git branch or tag could contain slashes (e.g `feature/foo`) so it's split first
+        let (pkg_name, tag) = pkg_name.split_once('@').unwrap_or((pkg_name, ""));
+
+        // Identifiers of packages installed from Git URLs are prefixed with the URL host
+        // (e.g `git.example.com/username/package_name`) so the package name is the last segment
+        let pkg_spec = pkg_name;
+        let (username, pkg_name) = match pkg_name.rsplit_once('/') {
+            Some(pkg_parts) => pkg_parts,
+            None => bail!(
                 "provide a valid package format. E.g username/package_name@(tag_name|branch_name)"
+            ),
+        };
+
+        // Path-like names (e.g `./foo/bar` or `/tmp/foo`) are local package directories
+        // which don't exist since they are not valid `username/package_name` values
+        let username = username.trim();
+        let pkg_name = pkg_name.trim();
+        let is_path_like = username.starts_with(['/', '.', '~'])
+            || username
+                .split('/')
+                .chain([pkg_name])
+                .any(|segment| matches!(segment.trim(), "" | "." | ".."));
+        if is_path_like {
+            bail!(
+                "package path directory \"{}\" doesn't exist or is inaccessible. Otherwise provide a valid package format. E.g username/package_name",
+                pkg_spec
             );
         }
 
-        let username = pkg_parts[0].trim();
-        let pkg_name_parts: Vec<&str> = pkg_parts[1].splitn(2, '@').collect();
-        if username.is_empty() || pkg_name_parts.is_empty() {
-            //  TODO: This message below is a workaround since either the `pacakge/name` format as well as
-            // a package dir path can have a "path-like" structure (name with slashes),
-            // however we could approach this differently in the future
-            bail!("provided package has not a valid `username/package_name` format or if it was a package path directory it doesn't exist or is inaccessible.");
+        if !tag.is_empty() {
+            pkg_tag = tag.trim();
         }
 
-        let pkg_name = pkg_name_parts[0].trim();
-        if pkg_name.is_empty() {
-            bail!("provide a valid package name value. E.g username/package_name");
-        }
+        Ok(Self {
+            user_name: username.into(),
+            pkg_name: pkg_name.into(),
+            pkg_tag: pkg_tag.into(),
+            pkg_commit: None,
+            pkg_path: None,
+            pkg_url: None,
+        })
+    }
 
-        if pkg_name_parts.len() == 2 && !pkg_name_parts[1].is_empty() {
-            pkg_tag = pkg_name_parts[1].trim();
-        }
+    /// Return a `PkgNameFmt` instance for an absolute local package directory path
+    /// which could not exist anymore (e.g an installed package whose directory was removed).
+    pub fn from_local_path(pkg_path: PathBuf) -> Result<Self> {
+        // We take the dirname as package name
+        let pkg_name = match pkg_path.file_name() {
+            Some(v) => v.to_string_lossy().into(),
+            None => bail!(
+                "directory name for path \"{}\" was not determined",
+                pkg_path.display(),
+            ),
+        };
 
         Ok(Self {
-            user_name: username.into(),
+            user_name: String::new(),
+            pkg_name,
+            pkg_tag: String::new(),
+            pkg_commit: None,
+            pkg_path: Some(pkg_path),
+            pkg_url: None,
+        })
+    }
+
+    /// Return a `PkgNameFmt` instance for a Git repository URL using a package identifier
+    /// made of the URL host and repository path. E.g `git.example.com/username/package_name`.
+    /// Formats: `https://`, `ssh://`, `file://` or `git@host:username/package_name.git`
+    /// optionally followed by `@(tag_name|branch_name)`.
+    fn from_git_url(pkg_name: &str) -> Result<Self> {
+        let pkg_name = pkg_name.trim();
+
+        // The Git branch or tag can only follow the last path segment
+        let last_sep = pkg_name.rfind(['/', ':']).unwrap_or_default();
+        let (pkg_url, pkg_tag) = match pkg_name[last_sep..].rfind('@') {
+            Some(i) => (
+                &pkg_name[..last_sep + i],
+                pkg_name[last_sep + i + 1..].trim(),
+            ),
+            None => (pkg_name, ""),
+        };
+
+        let (authority, repo_path) = match pkg_url.split_once("://") {
+            Some((_, rest)) => rest.split_once('/').unwrap_or((rest, "")),
+            None => pkg_url.split_once(':').unwrap_or((pkg_url, "")),
+        };
+
+        // Strip the user info and the port of the URL host
+        let host = authority.rsplit('@').next().unwrap_or_default();
+        let host = host.split(':').next().unwrap_or_default().to_lowercase();
+        let host = if host.is_empty() {
+            "localhost".into()
+        } else {
+            host
+        };
+
+        let repo_path = repo_path.trim_end_matches('/').trim_end_matches(".git");
+        let mut segments = repo_path
+            .split('/')
+            .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+            .collect::<Vec<_>>();
+        let pkg_name = match segments.pop() {
+            Some(pkg_name) => pkg_name,
+            None => bail!("git url `{}` has not a valid repository path.", pkg_url),
+        };
+
+        let mut user_name = vec![host.as_str()];
+        user_name.extend(segments);
+
+        Ok(Self {
+            user_name: user_name.join("/"),
             pkg_name: pkg_name.into(),
             pkg_tag: pkg_tag.into(),
+            pkg_commit: None,
             pkg_path: None,
+            pkg_url: Some(pkg_url.into()),
         })
     }
 
+    /// Return a `PkgNameFmt` instance for a `paket.toml` dependency along with its Git host provider.
+    /// The `package` property is used as the real package name when the dependency key is an alias
+    /// and `path` dependencies are resolved relative to the given dependent package directory.
+    pub fn from_dependency(
+        dep_name: &str,
+        dep: &TomlDependency,
+        pkg_dir: &Path,
+        git: &Git,
+    ) -> Result<(Self, String)> {
+        let real_name = dep.package.as_deref().unwrap_or(dep_name);
+        let pkg_ref = dep
+            .rev
+            .as_ref()
+            .or(dep.tag.as_ref())
+            .or(dep.branch.as_ref())
+            .or(dep.version.as_ref());
+
+        let mut provider = git.default_provider.clone();
+        let mut pkg_fmt = if let Some(path) = &dep.path {
+            let pkg_path = pkg_dir.join(path);
+            if !pkg_path.is_dir() {
+                bail!(
+                    "dependency path \"{}\" doesn't exist or is not a directory.",
+                    pkg_path.display()
+                );
+            }
+            return Self::from(&pkg_path.to_string_lossy()).map(|f| (f, provider));
+        } else if let Some(url) = &dep.git {
+            // Prefer the `username/package_name` format for known Git host providers
+            match git.get_provider_from_url(url) {
+                Ok((url_provider, user_repo_name)) => {
+                    provider = url_provider;
+                    Self::from(&user_repo_name)?
+                }
+                Err(_) => Self::from_git_url(url)?,
+            }
+        } else {
+            Self::from(real_name)?
+        };
+
+        if let Some(pkg_ref) = pkg_ref {
+            pkg_fmt.pkg_tag = pkg_ref.trim().to_owned();
+        }
+
+        Ok((pkg_fmt, provider))
+    }
+
+    /// Return the Git repository URL if the current package was given as one.
+    pub fn get_pkg_url(&self) -> Option<&str> {
+        self.pkg_url.as_deref()
+    }
+
     /// Return if the current package is a valid Git-based package directory path.
     pub fn get_pkg_path(&self) -> Option<PathBuf> {
         self.pkg_path.clone()
     }
 
+    /// Return a stable package identifier which is the package directory path
+    /// for local packages or the short name for remote ones.
+    pub fn get_id(&self) -> String {
+        match &self.pkg_path {
+            Some(pkg_path) => pkg_path.display().to_string(),
+            None => self.get_short_name(),
+        }
+    }
+
     /// Return the user and package name concatenated. E.g `username/package_name`.
     pub fn get_short_name(&self) -> String {
         [&self.user_name, "/", &self.pkg_name].concat()
     }
 }
+
+/// Check if a package name is a Git repository URL either with a scheme
+/// (e.g `https://`, `ssh://` or `file://`) or using the SCP-like syntax (e.g `git@host:path`).
+fn is_git_url(pkg_name: &str) -> bool {
+    if pkg_name.contains("://") {
+        return true;
+    }
+    match pkg_name.split_once(':') {
+        Some((authority, path)) => {
+            !authority.is_empty() && !authority.contains('/') && !path.is_empty()
+        }
+        None => false,
+    }
+}
+
+/// Return the semver requirement of a Git branch or tag given either as `latest`
+/// or as a version requirement starting with an operator (e.g `^2.4`, `~2.5.0` or `>=2.0, <3.0`).
+/// Plain versions (e.g `2.4.0`) are kept as literal Git tags.
+pub fn parse_version_req(pkg_tag: &str) -> Result<Option<VersionReq>> {
+    let pkg_tag = pkg_tag.trim();
+    if pkg_tag == "latest" {
+        return Ok(Some(VersionReq::STAR));
+    }
+    if !pkg_tag.starts_with(['^', '~', '=', '>', '<', '*']) {
+        return Ok(None);
+    }
+
+    match VersionReq::parse(pkg_tag) {
+        Ok(req) => Ok(Some(req)),
+        Err(err) => bail!("version requirement `{}` is not valid: {}", pkg_tag, err),
+    }
+}
+
+/// Check if a Git branch or tag looks like a full or abbreviated commit hash.
+pub fn is_commit_sha(pkg_tag: &str) -> bool {
+    (7..=40).contains(&pkg_tag.len()) && pkg_tag.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git;
+
+    #[test]
+    fn from_short_name() {
+        let pkg_fmt = PkgNameFmt::from("joseluisq/gitnow").unwrap();
+        assert_eq!(pkg_fmt.user_name, "joseluisq");
+        assert_eq!(pkg_fmt.pkg_name, "gitnow");
+        assert_eq!(pkg_fmt.pkg_tag, "");
+        assert_eq!(pkg_fmt.get_id(), "joseluisq/gitnow");
+        assert!(pkg_fmt.get_pkg_url().is_none());
+    }
+
+    #[test]
+    fn from_short_name_with_tag() {
+        let pkg_fmt = PkgNameFmt::from("joseluisq/gitnow@2.4.0").unwrap();
+        assert_eq!(pkg_fmt.pkg_name, "gitnow");
+        assert_eq!(pkg_fmt.pkg_tag, "2.4.0");
+
+        let pkg_fmt = PkgNameFmt::from("joseluisq/gitnow@feature/foo").unwrap();
+        assert_eq!(pkg_fmt.get_id(), "joseluisq/gitnow");
+        assert_eq!(pkg_fmt.pkg_tag, "feature/foo");
+    }
+
+    #[test]
+    fn from_invalid_names() {
+        assert!(PkgNameFmt::from("").is_err());
+        assert!(PkgNameFmt::from("gitnow").is_err());
+        assert!(PkgNameFmt::from("joseluisq/").is_err());
+        assert!(PkgNameFmt::from("/gitnow").is_err());
+        assert!(PkgNameFmt::from("joseluisq/gitnow/").is_err());
+    }
+
+    #[test]
+    fn from_missing_local_paths() {
+        let cases = [
+            "/tmp/nonexistent/plugin",
+            "./foo/bar",
+            "../foo/bar",
+            "~/foo/bar",
+            ".hidden/bar",
+            "foo//bar",
+            "foo/./bar",
+            "foo/../bar",
+            "foo/..",
+            "foo/.",
+        ];
+        for name in cases {
+            match PkgNameFmt::from(name) {
+                Ok(_) => panic!("`{}` should not be parsed", name),
+                Err(err) => assert!(err.to_string().contains("doesn't exist"), "{}", name),
+            }
+        }
+    }
+
+    #[test]
+    fn from_git_url_formats() {
+        let cases = [
+            (
+                "https://git.example.com/joseluisq/gitnow.git",
+                "git.example.com/joseluisq/gitnow",
+            ),
+            (
+                "ssh://git@git.example.com:2222/group/sub/gitnow",
+                "git.example.com/group/sub/gitnow",
+            ),
+            (
+                "git@git.example.com:joseluisq/gitnow.git",
+                "git.example.com/joseluisq/gitnow",
+            ),
+            ("file:///srv/git/gitnow", "localhost/srv/git/gitnow"),
+        ];
+        for (url, id) in cases {
+            let pkg_fmt = PkgNameFmt::from(url).unwrap();
+            assert_eq!(pkg_fmt.pkg_name, "gitnow", "{}", url);
+            assert_eq!(pkg_fmt.get_id(), id, "{}", url);
+            assert_eq!(pkg_fmt.get_pkg_url(), Some(url));
+        }
+    }
+
+    #[test]
+    fn from_git_url_with_tag() {
+        let pkg_fmt = PkgNameFmt::from("https://git.example.com/joseluisq/gitnow@v2.4.0").unwrap();
+        assert_eq!(pkg_fmt.pkg_tag, "v2.4.0");
+        assert_eq!(
+            pkg_fmt.get_pkg_url(),
+            Some("https://git.example.com/joseluisq/gitnow")
+        );
+
+        let pkg_fmt = PkgNameFmt::from("git@git.example.com:joseluisq/gitnow.git@main").unwrap();
+        assert_eq!(pkg_fmt.pkg_tag, "main");
+        assert_eq!(pkg_fmt.get_id(), "git.example.com/joseluisq/gitnow");
+    }
+
+    #[test]
+    fn git_url_id_round_trip() {
+        let urls = [
+            "https://git.example.com/joseluisq/gitnow.git",
+            "ssh://git@git.example.com:2222/group/sub/gitnow",
+            "git@git.example.com:joseluisq/gitnow.git",
+            "file:///srv/git/gitnow",
+        ];
+        for url in urls {
+            let pkg_fmt = PkgNameFmt::from_git_url(url).unwrap();
+            let parsed = PkgNameFmt::from(&pkg_fmt.get_id()).unwrap();
+            assert_eq!(parsed.user_name, pkg_fmt.user_name, "{}", url);
+            assert_eq!(parsed.pkg_name, pkg_fmt.pkg_name, "{}", url);
+            assert_eq!(parsed.get_id(), pkg_fmt.get_id(), "{}", url);
+            assert_eq!(parsed.get_short_name(), pkg_fmt.get_short_name(), "{}", url);
+
+            let parsed = PkgNameFmt::from(&[&pkg_fmt.get_id(), "@main"].concat()).unwrap();
+            assert_eq!(parsed.pkg_name, "gitnow", "{}", url);
+            assert_eq!(parsed.pkg_tag, "main", "{}", url);
+        }
+    }
+    #[test]
+    fn parse_version_requirements() {
+        let cases = [
+            ("latest", Some("*")),
+            (" latest ", Some("*")),
+            ("^2.4", Some("^2.4")),
+            ("~2.5.0", Some("~2.5.0")),
+            (">=1.0, <2.0", Some(">=1.0, <2.0")),
+            ("*", Some("*")),
+            ("2.4.0", None),
+            ("v2.4.0", None),
+            ("main", None),
+            ("feature/foo", None),
+            ("", None),
+        ];
+        for (pkg_tag, expected) in cases {
+            let req = parse_version_req(pkg_tag).unwrap().map(|r| r.to_string());
+            assert_eq!(req.as_deref(), expected, "{:?}", pkg_tag);
+        }
+
+        assert!(parse_version_req("^foo").is_err());
+        assert!(parse_version_req(">=").is_err());
+    }
+
+    #[test]
+    fn tag_versions() {
+        let cases = [
+            ("2.4.0", Some("2.4.0")),
+            ("v2.4.0", Some("2.4.0")),
+            ("v1.0.0-beta.1", Some("1.0.0-beta.1")),
+            ("2.4", None),
+            ("release-2.4.0", None),
+            ("vv2.4.0", None),
+            ("main", None),
+        ];
+        for (tag, expected) in cases {
+            let version = git::tag_version(tag).map(|v| v.to_string());
+            assert_eq!(version.as_deref(), expected, "{:?}", tag);
+        }
+    }
+
+    #[test]
+    fn highest_matching_tags() {
+        let refs = [
+            "refs/heads/main",
+            "refs/heads/3.0.0",
+            "refs/tags/1.0.0",
+            "refs/tags/v2.4.0",
+            "refs/tags/2.4.1",
+            "refs/tags/v2.5.0",
+            "refs/tags/v3.0.0-beta.1",
+            "refs/tags/nightly",
+        ]
+        .map(String::from);
+        let cases = [
+            ("latest", Some("v2.5.0")),
+            ("^2.4", Some("v2.5.0")),
+            ("~2.4.0", Some("2.4.1")),
+            ("=2.4.0", Some("v2.4.0")),
+            ("<2.0", Some("1.0.0")),
+            (">=3.0.0-beta.1", Some("v3.0.0-beta.1")),
+            ("^3", None),
+            (">=4", None),
+        ];
+        for (pkg_tag, expected) in cases {
+            let req = parse_version_req(pkg_tag).unwrap().unwrap();
+            assert_eq!(
+                git::highest_matching_tag(&refs, &req),
+                expected,
+                "{:?}",
+                pkg_tag
+            );
+        }
+
+        assert!(git::highest_matching_tag(&[], &VersionReq::STAR).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use crate::helpers::file;
+use crate::pkg::receipt::{ReceiptSource, Receipts};
+use crate::result::{Context, Result};
+
+/// File name of the lockfile placed on the Paket directory.
+pub const LOCK_FILE_NAME: &str = "paket.lock";
+
+/// Current lockfile format version.
+const LOCK_VERSION: u32 = 1;
+
+/// A package pinned to an exact Git commit.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedPackage {
+    /// Package identifier.
+    pub id: String,
+    /// Package name as defined on its `paket.toml` file.
+    pub name: String,
+    /// Package version as defined on its `paket.toml` file.
+    pub version: semver::Version,
+    /// Resolved package source.
+    pub source: ReceiptSource,
+    /// Git host provider used to install the package (if any).
+    pub provider: Option<String>,
+    /// Requested Git branch or tag (if any) or the tag resolved from a version requirement.
+    pub reference: Option<String>,
+    /// Requested semver requirement of the Git tag (if any). E.g `^2.4` or `latest`.
+    pub requirement: Option<String>,
+    /// Resolved Git commit of the installed package.
+    pub commit: Option<String>,
+    /// SHA-256 checksum of all installed package files.
+    pub checksum: String,
+    /// Identifiers of the packages this package depends on.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// Lockfile recording the exact state of every installed package.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Lockfile {
+    /// Lockfile format version.
+    pub version: u32,
+    /// Locked packages.
+    #[serde(default)]
+    pub package: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    /// Create a lockfile based on the current installation receipts.
+    /// Linked packages are left out since they just point to local working directories.
+    pub fn from_receipts(receipts: &Receipts) -> Self {
+        let package = receipts
+            .packages
+            .iter()
+            .filter(|(_, receipt)| !receipt.is_linked())
+            .map(|(id, receipt)| LockedPackage {
+                id: id.to_owned(),
+                name: receipt.name.clone(),
+                version: receipt.version.clone(),
+                source: receipt.source.clone(),
+                provider: receipt.provider.clone(),
+                reference: receipt.reference.clone(),
+                requirement: receipt.requirement.clone(),
+                commit: receipt.commit.clone(),
+                checksum: receipt.checksum(),
+                dependencies: receipt.dependencies.clone(),
+            })
+            .collect();
+
+        Self {
+            version: LOCK_VERSION,
+            package,
+        }
+    }
+
+    /// Return the lockfile path of a given Paket directory.
+    pub fn path(paket_dir: &Path) -> PathBuf {
+        paket_dir.join(LOCK_FILE_NAME)
+    }
+
+    /// Load the lockfile of a given Paket directory.
+    pub fn load(paket_dir: &Path) -> Result<Self> {
+        let path = Self::path(paket_dir);
+        if !path.exists() {
+            bail!(
+                "lockfile was not found at \"{}\". Install some packages first.",
+                path.display()
+            );
+        }
+
+        let data = file::read(&path)?;
+        let lockfile: Lockfile = toml::from_str(&data)
+            .with_context(|| format!("lockfile at \"{}\" could not be parsed.", path.display()))?;
+        if lockfile.version != LOCK_VERSION {
+            bail!(
+                "lockfile version `{}` is not supported. Expected version `{}`.",
+                lockfile.version,
+                LOCK_VERSION
+            );
+        }
+
+        Ok(lockfile)
+    }
+
+    /// Write the lockfile into a given Paket directory.
+    pub fn save(&self, paket_dir: &Path) -> Result {
+        let path = Self::path(paket_dir);
+        let data = toml::to_string(self).with_context(|| "lockfile could not be serialized.")?;
+        file::write_atomic(&path, &data)
+            .with_context(|| format!("lockfile at \"{}\" could not be written.", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Return the locked packages sorted so every package comes after its dependencies.
+    pub fn sorted_packages(&self) -> Vec<&LockedPackage> {
+        let mut sorted: Vec<&LockedPackage> = vec![];
+        let mut pending = self.package.iter().collect::<Vec<_>>();
+
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|p| {
+                p.dependencies.iter().all(|d| {
+                    sorted.iter().any(|s| &s.id == d) || !self.package.iter().any(|s| &s.id == d)
+                })
+            });
+
+            // Keep the remaining packages in their original order if there is a cycle
+            if ready.is_empty() {
+                sorted.extend(rest);
+                break;
+            }
+
+            sorted.extend(ready);
+            pending = rest;
+        }
+
+        sorted
+    }
+}
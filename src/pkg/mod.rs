@@ -1,2 +1,5 @@
 pub mod config;
 pub mod fmt;
+pub mod lock;
+pub mod packages;
+pub mod receipt;
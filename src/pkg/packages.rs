@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use crate::helpers::file;
+use crate::pkg::config;
+use crate::result::Result;
+
+/// File name of the declarative package list placed on the Paket directory.
+pub const PACKAGES_FILE_NAME: &str = "packages.toml";
+
+/// Declarative list of packages which should be installed on the system.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct TomlPackages {
+    /// Package specs using the `username/package_name@(tag_name|branch_name)`
+    /// format or local package directory paths.
+    #[serde(default)]
+    pub packages: Vec<String>,
+}
+
+impl TomlPackages {
+    /// Return the default package list file path of a given Paket directory.
+    pub fn path(paket_dir: &Path) -> PathBuf {
+        paket_dir.join(PACKAGES_FILE_NAME)
+    }
+
+    /// Read a package list file expanding the `~` of local package directory paths.
+    pub fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            bail!("package list file \"{}\" was not found.", path.display());
+        }
+
+        let mut list: TomlPackages = config::read_toml_with_warnings(path, "package list")?;
+        list.packages = list
+            .packages
+            .iter()
+            .map(|spec| file::expand_tilde(spec.trim()))
+            .filter(|spec| !spec.is_empty())
+            .collect();
+
+        Ok(list)
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::git::RefKind;
+use crate::helpers::file;
+use crate::result::{Context, Result};
+
+/// File name of the installation receipts database placed on the Paket directory.
+pub const RECEIPTS_FILE_NAME: &str = "receipts.toml";
+
+/// Describes where an installed package comes from.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ReceiptSource {
+    /// A package cloned from a remote Git repository.
+    Remote {
+        /// Remote repository URL.
+        url: String,
+    },
+    /// A package installed from a local Git repository directory.
+    Local {
+        /// Local package directory path.
+        path: PathBuf,
+    },
+}
+
+/// A file written into the Fish shell directories by a package.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptFile {
+    /// Destination file path.
+    pub path: PathBuf,
+    /// SHA-256 checksum of the file contents at installation time.
+    pub hash: String,
+    /// Whether the file is a symbolic link to the package file instead of a copy.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub linked: bool,
+    /// File replaced by this one when the package was installed with `--force` (if any).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub displaced: Option<DisplacedFile>,
+}
+
+/// A file replaced by a package installed with `--force` which is restored when
+/// the package no longer provides it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DisplacedFile {
+    /// Copy of the replaced file contents.
+    pub copy: PathBuf,
+    /// Identifier of the package which provided the replaced file or none for a user file.
+    pub owner: Option<String>,
+    /// File replaced in turn by the replaced one (if any).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<Box<DisplacedFile>>,
+}
+
+/// Installation receipt of a single package.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct Receipt {
+    /// Package name as defined on its `paket.toml` file.
+    pub name: String,
+    /// Package version as defined on its `paket.toml` file.
+    pub version: semver::Version,
+    /// Resolved package source.
+    pub source: ReceiptSource,
+    /// Git host provider used to install the package (if any).
+    pub provider: Option<String>,
+    /// Requested Git branch or tag (if any) or the tag resolved from a version requirement.
+    pub reference: Option<String>,
+    /// Requested semver requirement of the Git tag (if any). E.g `^2.4` or `latest`.
+    pub requirement: Option<String>,
+    /// Kind of the requested Git reference (if known).
+    pub ref_kind: Option<RefKind>,
+    /// Resolved Git commit of the installed package.
+    pub commit: Option<String>,
+    /// Identifiers of the packages this package depends on.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Files written into the Fish shell directories.
+    #[serde(default)]
+    pub files: Vec<ReceiptFile>,
+}
+
+impl Receipt {
+    /// Check if the package files are symbolic links to a local package directory.
+    pub fn is_linked(&self) -> bool {
+        self.files.iter().any(|f| f.linked)
+    }
+
+    /// Compute a SHA-256 checksum of all installed files which doesn't depend on
+    /// the location of the Fish shell directories.
+    pub fn checksum(&self) -> String {
+        let mut entries = self
+            .files
+            .iter()
+            .map(|f| {
+                let dir = f
+                    .path
+                    .parent()
+                    .and_then(|p| p.file_name())
+                    .unwrap_or_default();
+                let name = f.path.file_name().unwrap_or_default();
+                format!(
+                    "{}/{} {}",
+                    dir.to_string_lossy(),
+                    name.to_string_lossy(),
+                    f.hash
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        file::checksum_bytes(entries.join("\n").as_bytes())
+    }
+}
+
+/// Persistent database of installation receipts keyed by package identifier.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct Receipts {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Installed packages.
+    #[serde(default)]
+    pub packages: BTreeMap<String, Receipt>,
+}
+
+impl Receipts {
+    /// Load the receipts database from a given Paket directory or create an empty one.
+    pub fn load(paket_dir: &Path) -> Result<Self> {
+        let path = paket_dir.join(RECEIPTS_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self {
+                path,
+                ..Default::default()
+            });
+        }
+
+        let data = file::read(&path)?;
+        let mut receipts: Receipts = toml::from_str(&data).with_context(|| {
+            format!(
+                "installation receipts file at \"{}\" could not be parsed.",
+                path.display()
+            )
+        })?;
+        receipts.path = path;
+
+        Ok(receipts)
+    }
+
+    /// Write the receipts database back to its file.
+    pub fn save(&self) -> Result {
+        let data = toml::to_string(self)
+            .with_context(|| "installation receipts could not be serialized.")?;
+        file::write_atomic(&self.path, &data).with_context(|| {
+            format!(
+                "installation receipts file at \"{}\" could not be written.",
+                self.path.display()
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Return the path of the receipts database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the identifier of the installed package (other than a given one)
+    /// which provides a given file (if any).
+    pub fn owner(&self, path: &Path, except_pkg_id: &str) -> Option<&str> {
+        self.packages
+            .iter()
+            .find(|(id, r)| *id != except_pkg_id && r.files.iter().any(|f| f.path == path))
+            .map(|(id, _)| id.as_str())
+    }
+
+    /// Detach a file from the receipt of a given package returning it (if any).
+    pub fn take_file(&mut self, pkg_id: &str, path: &Path) -> Option<ReceiptFile> {
+        let files = &mut self.packages.get_mut(pkg_id)?.files;
+        let pos = files.iter().position(|f| f.path == path)?;
+        Some(files.remove(pos))
+    }
+
+    /// Return the receipt of a given package identifier.
+    pub fn get(&self, pkg_id: &str) -> Option<&Receipt> {
+        self.packages.get(pkg_id)
+    }
+
+    /// Insert or replace the receipt of a given package identifier.
+    pub fn insert(&mut self, pkg_id: &str, receipt: Receipt) {
+        self.packages.insert(pkg_id.to_owned(), receipt);
+    }
+
+    /// Remove the receipt of a given package identifier returning it.
+    pub fn remove(&mut self, pkg_id: &str) -> Option<Receipt> {
+        self.packages.remove(pkg_id)
+    }
+
+    /// Return the identifiers of the installed packages depending on a given package.
+    pub fn dependents(&self, pkg_id: &str) -> Vec<String> {
+        self.packages
+            .iter()
+            .filter(|(id, r)| id.as_str() != pkg_id && r.dependencies.iter().any(|d| d == pkg_id))
+            .map(|(id, _)| id.to_owned())
+            .collect()
+    }
+
+    /// Return the given package identifiers along with all their installed dependencies.
+    pub fn dependency_closure(&self, pkg_ids: &[String]) -> BTreeSet<String> {
+        let mut closure = pkg_ids.iter().cloned().collect::<BTreeSet<_>>();
+        let mut pending = pkg_ids.to_vec();
+
+        while let Some(pkg_id) = pending.pop() {
+            if let Some(receipt) = self.get(&pkg_id) {
+                for dep_id in &receipt.dependencies {
+                    if closure.insert(dep_id.clone()) {
+                        pending.push(dep_id.clone());
+                    }
+                }
+            }
+        }
+
+        closure
+    }
+
+    /// Sort the given package identifiers so every package comes before its dependencies.
+    pub fn removal_order(&self, pkg_ids: Vec<String>) -> Vec<String> {
+        let mut sorted = vec![];
+        let mut pending = pkg_ids;
+
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending.iter().cloned().partition(|pkg_id| {
+                !pending.iter().any(|other| {
+                    other != pkg_id
+                        && self
+                            .get(other)
+                            .is_some_and(|r| r.dependencies.contains(pkg_id))
+                })
+            });
+
+            // Keep the remaining packages in their original order if there is a cycle
+            if ready.is_empty() {
+                sorted.extend(rest);
+                break;
+            }
+
+            sorted.extend(ready);
+            pending = rest;
+        }
+
+        sorted
+    }
+}
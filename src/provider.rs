@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use crate::result::Result;
+
+/// Built-in Git host providers mapped to their host names.
+const BUILTIN_PROVIDERS: [(&str, &str); 3] = [
+    ("github", "github.com"),
+    ("bitbucket", "bitbucket.org"),
+    ("gitlab", "gitlab.com"),
+];
+
+/// Registry of Git host providers mapped to their repository URL templates.
+///
+/// A template uses the `{user}` and `{repo}` placeholders.
+/// E.g `https://git.example.com/{user}/{repo}.git` or `git@git.example.com:{user}/{repo}.git`
+#[derive(Clone, Debug, Default)]
+pub struct Providers {
+    templates: BTreeMap<String, String>,
+}
+
+impl Providers {
+    /// Create a new registry containing the built-in providers along with custom ones
+    /// mapped either to host names or URL templates. Custom providers take precedence.
+    pub fn new(custom: &BTreeMap<String, String>) -> Result<Self> {
+        let mut templates = BUILTIN_PROVIDERS
+            .iter()
+            .map(|(name, host)| (name.to_string(), Self::host_template(host)))
+            .collect::<BTreeMap<_, _>>();
+
+        for (name, value) in custom {
+            let value = value.trim();
+            let template = if value.contains("{repo}") {
+                value.to_owned()
+            } else if value.is_empty() || value.contains(['/', ':', '{', '}']) {
+                bail!(
+                    "git host provider `{}` should be either a host name or an url template containing `{{user}}` and `{{repo}}` placeholders",
+                    name
+                );
+            } else {
+                Self::host_template(value)
+            };
+            templates.insert(name.to_owned(), template);
+        }
+
+        Ok(Self { templates })
+    }
+
+    /// Return the default HTTPS URL template of a host name.
+    fn host_template(host: &str) -> String {
+        ["https://", host, "/{user}/{repo}.git"].concat()
+    }
+
+    /// Return the repository URL of a `username/repository` name on a given provider.
+    pub fn endpoint(&self, provider: &str, user_repo_name: &str) -> Option<String> {
+        let template = self.templates.get(provider)?;
+        let (user, repo) = user_repo_name
+            .split_once('/')
+            .unwrap_or(("", user_repo_name));
+
+        Some(template.replace("{user}", user).replace("{repo}", repo))
+    }
+
+    /// Return the provider name and the `username/repository` name of a repository URL
+    /// matching one of the registered templates. HTTPS, SSH and SCP-like (`git@host:path`)
+    /// URLs of the same repository match the same template.
+    pub fn find_by_url(&self, url: &str) -> Option<(String, String)> {
+        let url = normalize_url(url);
+
+        self.templates.iter().find_map(|(name, template)| {
+            let template = normalize_url(template);
+            let (prefix, suffix) = template.split_once("{user}/{repo}")?;
+            let user_repo_name = url.strip_prefix(prefix)?.strip_suffix(suffix)?;
+
+            let segments = user_repo_name.split('/').collect::<Vec<_>>();
+            if segments.len() != 2 || segments.iter().any(|s| s.is_empty()) {
+                return None;
+            }
+
+            Some((name.to_owned(), user_repo_name.to_owned()))
+        })
+    }
+}
+
+/// Return a repository URL (or URL template) as `host/path` without its scheme, user info,
+/// port and `.git` suffix. E.g `git@github.com:user/repo.git` becomes `github.com/user/repo`
+fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').unwrap_or((rest, "")),
+        None => url.split_once(':').unwrap_or(("", url)),
+    };
+
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default().to_lowercase();
+    [&host, "/", path.trim_matches('/')].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn providers() -> Providers {
+        let custom = BTreeMap::from([
+            ("example".to_owned(), "git.example.com".to_owned()),
+            (
+                "corp".to_owned(),
+                "ssh://git@git.corp.com:2222/{user}/{repo}.git".to_owned(),
+            ),
+        ]);
+        Providers::new(&custom).unwrap()
+    }
+
+    #[test]
+    fn endpoint_formats() {
+        let providers = providers();
+        let cases = [
+            ("github", "https://github.com/joseluisq/gitnow.git"),
+            ("gitlab", "https://gitlab.com/joseluisq/gitnow.git"),
+            ("example", "https://git.example.com/joseluisq/gitnow.git"),
+            ("corp", "ssh://git@git.corp.com:2222/joseluisq/gitnow.git"),
+        ];
+        for (provider, endpoint) in cases {
+            assert_eq!(
+                providers.endpoint(provider, "joseluisq/gitnow").as_deref(),
+                Some(endpoint),
+                "{}",
+                provider
+            );
+        }
+        assert!(providers.endpoint("unknown", "joseluisq/gitnow").is_none());
+    }
+
+    #[test]
+    fn invalid_custom_providers() {
+        for value in ["", "git.example.com/path", "host:22", "{user}"] {
+            let custom = BTreeMap::from([("bad".to_owned(), value.to_owned())]);
+            assert!(Providers::new(&custom).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn find_by_url_formats() {
+        let providers = providers();
+        let cases = [
+            ("https://github.com/joseluisq/gitnow.git", "github"),
+            ("https://github.com/joseluisq/gitnow", "github"),
+            ("https://github.com/joseluisq/gitnow/", "github"),
+            ("https://GitHub.com/joseluisq/gitnow.git", "github"),
+            ("git@github.com:joseluisq/gitnow.git", "github"),
+            ("git@github.com:joseluisq/gitnow", "github"),
+            ("ssh://git@github.com/joseluisq/gitnow.git", "github"),
+            ("git@git.example.com:joseluisq/gitnow.git", "example"),
+            ("https://git.corp.com/joseluisq/gitnow", "corp"),
+            ("ssh://git@git.corp.com:2222/joseluisq/gitnow.git", "corp"),
+        ];
+        for (url, provider) in cases {
+            assert_eq!(
+                providers.find_by_url(url),
+                Some((provider.to_owned(), "joseluisq/gitnow".to_owned())),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn find_by_url_no_match() {
+        let providers = providers();
+        let urls = [
+            "https://unknown.com/joseluisq/gitnow.git",
+            "https://github.com/joseluisq",
+            "https://github.com/group/sub/gitnow.git",
+            "git@github.com:gitnow.git",
+            "file:///srv/git/gitnow",
+        ];
+        for url in urls {
+            assert!(providers.find_by_url(url).is_none(), "{}", url);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+
+use crate::git::DEFAULT_PROVIDER;
+use crate::helpers::file;
+use crate::pkg::config;
+use crate::result::{Context, Result};
+
+/// File name of the Paket configuration file.
+pub const SETTINGS_FILE_NAME: &str = "config.toml";
+
+/// Maximum number of concurrent Git operations used when no one is specified.
+pub const DEFAULT_JOBS: usize = 4;
+
+/// Directory overrides of the Paket configuration file.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct SettingsPaths {
+    /// Fish configuration directory. Defaults to `~/.config/fish`.
+    pub fish_dir: Option<PathBuf>,
+    /// Paket directory. Defaults to `~/.config/paket`.
+    pub paket_dir: Option<PathBuf>,
+    /// Paket cache directory. Defaults to `~/.cache/paket`.
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// Fish shell events behavior of the Paket configuration file.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct SettingsEvents {
+    /// Emit Fish shell events. Defaults to `true`.
+    pub enabled: Option<bool>,
+    /// Make a failing Fish shell event fail the whole command. Defaults to `true`.
+    pub fatal: Option<bool>,
+}
+
+/// Paket configuration file (`~/.config/paket/config.toml`).
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct Settings {
+    /// Git host provider used when no one is specified. Defaults to `github`.
+    pub default_provider: Option<String>,
+    /// Git branch or tag used when no one is specified.
+    /// Defaults to the default branch of the remote repository.
+    pub default_ref: Option<String>,
+    /// Maximum number of concurrent Git operations when handling several packages. Defaults to `4`.
+    pub jobs: Option<usize>,
+    /// Install packages only from the repository cache without network access. Defaults to `false`.
+    pub offline: Option<bool>,
+    /// Custom Git host providers mapped to their host names or URL templates.
+    /// E.g `codeberg = "codeberg.org"` or `corp = "git@git.corp.example:{user}/{repo}.git"`
+    #[serde(default)]
+    pub providers: BTreeMap<String, String>,
+    /// Directory overrides.
+    #[serde(default)]
+    pub paths: SettingsPaths,
+    /// Fish shell events behavior.
+    #[serde(default)]
+    pub events: SettingsEvents,
+}
+
+impl Settings {
+    /// Load the Paket configuration file (if any) and apply the environment variable overrides.
+    ///
+    /// The file is read from `PAKET_CONFIG` or `~/.config/paket/config.toml` and the
+    /// `PAKET_DEFAULT_PROVIDER`, `PAKET_DEFAULT_REF`, `PAKET_JOBS`, `PAKET_OFFLINE`, `PAKET_FISH_DIR`,
+    /// `PAKET_DIR`, `PAKET_CACHE_DIR`, `PAKET_EVENTS` and `PAKET_EVENTS_FATAL` variables take
+    /// precedence over it.
+    pub fn load() -> Result<Self> {
+        let path = match env_var("PAKET_CONFIG") {
+            Some(path) => PathBuf::from(file::expand_tilde(&path)),
+            None => match dirs::home_dir() {
+                Some(home_dir) => home_dir.join(".config/paket").join(SETTINGS_FILE_NAME),
+                None => return Ok(Settings::default()),
+            },
+        };
+
+        let mut settings = if path.is_file() {
+            config::read_toml_with_warnings::<Settings>(&path, "configuration").with_context(
+                || {
+                    format!(
+                        "configuration file \"{}\" could not be read.",
+                        path.display()
+                    )
+                },
+            )?
+        } else {
+            Settings::default()
+        };
+
+        if let Some(provider) = env_var("PAKET_DEFAULT_PROVIDER") {
+            settings.default_provider = Some(provider);
+        }
+        if let Some(reference) = env_var("PAKET_DEFAULT_REF") {
+            settings.default_ref = Some(reference);
+        }
+        if let Some(jobs) = env_var("PAKET_JOBS") {
+            match jobs.parse() {
+                Ok(jobs) => settings.jobs = Some(jobs),
+                Err(_) => bail!(
+                    "environment variable `PAKET_JOBS` has an invalid number value `{}`",
+                    jobs
+                ),
+            }
+        }
+        if let Some(offline) = env_var_bool("PAKET_OFFLINE")? {
+            settings.offline = Some(offline);
+        }
+        if let Some(dir) = env_var("PAKET_FISH_DIR") {
+            settings.paths.fish_dir = Some(dir.into());
+        }
+        if let Some(dir) = env_var("PAKET_DIR") {
+            settings.paths.paket_dir = Some(dir.into());
+        }
+        if let Some(dir) = env_var("PAKET_CACHE_DIR") {
+            settings.paths.cache_dir = Some(dir.into());
+        }
+        if let Some(enabled) = env_var_bool("PAKET_EVENTS")? {
+            settings.events.enabled = Some(enabled);
+        }
+        if let Some(fatal) = env_var_bool("PAKET_EVENTS_FATAL")? {
+            settings.events.fatal = Some(fatal);
+        }
+
+        // Expand the `~` of directory overrides
+        let expand = |p: &PathBuf| PathBuf::from(file::expand_tilde(&p.to_string_lossy()));
+        settings.paths.fish_dir = settings.paths.fish_dir.as_ref().map(expand);
+        settings.paths.paket_dir = settings.paths.paket_dir.as_ref().map(expand);
+        settings.paths.cache_dir = settings.paths.cache_dir.as_ref().map(expand);
+
+        Ok(settings)
+    }
+
+    /// Return the Git host provider used when no one is specified.
+    pub fn default_provider(&self) -> &str {
+        self.default_provider.as_deref().unwrap_or(DEFAULT_PROVIDER)
+    }
+
+    /// Return the Git branch or tag used when no one is specified (if configured).
+    pub fn default_ref(&self) -> Option<&str> {
+        self.default_ref.as_deref()
+    }
+
+    /// Return the maximum number of concurrent Git operations.
+    pub fn jobs(&self) -> usize {
+        self.jobs.unwrap_or(DEFAULT_JOBS).max(1)
+    }
+
+    /// Return if packages should be installed only from the repository cache.
+    pub fn offline(&self) -> bool {
+        self.offline.unwrap_or(false)
+    }
+
+    /// Return if Fish shell events should be emitted.
+    pub fn events_enabled(&self) -> bool {
+        self.events.enabled.unwrap_or(true)
+    }
+
+    /// Return if a failing Fish shell event should fail the whole command.
+    pub fn events_fatal(&self) -> bool {
+        self.events.fatal.unwrap_or(true)
+    }
+}
+
+/// Return the value of a non-empty environment variable.
+fn env_var(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .map(|v| v.trim().to_owned())
+        .filter(|v| !v.is_empty())
+}
+
+/// Return the boolean value of a non-empty environment variable.
+fn env_var_bool(name: &str) -> Result<Option<bool>> {
+    match env_var(name).as_deref() {
+        None => Ok(None),
+        Some("1" | "true" | "yes" | "on") => Ok(Some(true)),
+        Some("0" | "false" | "no" | "off") => Ok(Some(false)),
+        Some(v) => bail!(
+            "environment variable `{}` has an invalid boolean value `{}`",
+            name,
+            v
+        ),
+    }
+}
@@ -0,0 +1,350 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git::Git;
+use crate::helpers::file;
+use crate::result::{Context, Result};
+
+/// File name of the journal of the running transaction placed on the Paket directory.
+pub const JOURNAL_FILE_NAME: &str = "transaction.toml";
+
+/// Suffix of the files staged next to their destinations before being moved into place.
+const STAGED_SUFFIX: &str = ".paket-new";
+
+/// Suffix of the files or directories kept next to their original paths until a transaction
+/// is committed.
+const BACKUP_SUFFIX: &str = ".paket-old";
+
+/// A change applied by a transaction which can be reverted.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Change {
+    /// A file was written or removed. Its previous content (if any) is kept on a backup file.
+    File {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        backup: Option<PathBuf>,
+    },
+    /// A file was staged next to its destination before being moved into place.
+    Staged { path: PathBuf },
+    /// A package repository was cloned into a new directory.
+    Clone { repo_dir: PathBuf },
+    /// A package repository was removed. It's kept on a backup directory.
+    Removal { repo_dir: PathBuf, backup: PathBuf },
+    /// A package repository was checked out from a given commit.
+    Checkout {
+        user_repo_name: String,
+        commit: String,
+    },
+}
+
+/// Journal of the changes performed by a package installation or update.
+///
+/// Files are staged next to their destinations first and then moved into place keeping
+/// a backup of the files they overwrite, so a failing operation can restore the previous
+/// state exactly via `rollback` while `commit` just drops the backups.
+///
+/// Every change is written to a journal file before it is applied, so the transaction
+/// of an interrupted process can be finished or rolled back later via `recover`.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Transaction {
+    #[serde(skip)]
+    journal: Option<PathBuf>,
+    /// Whether the changes were made permanent so only the backups are left to remove.
+    #[serde(default)]
+    committed: bool,
+    #[serde(default)]
+    changes: Vec<Change>,
+}
+
+impl Transaction {
+    /// Start a transaction recording its changes on a given journal file.
+    pub fn new(journal: PathBuf) -> Self {
+        Self {
+            journal: Some(journal),
+            ..Default::default()
+        }
+    }
+
+    /// Finish the transaction of an interrupted process found on a given journal file.
+    /// A committed transaction just drops its remaining backups, otherwise it is rolled back.
+    pub fn recover(journal: &Path, git: &Git) -> Result {
+        let data = file::read(journal)?;
+        let mut tx: Transaction = toml::from_str(&data).with_context(|| {
+            format!(
+                "transaction journal file at \"{}\" could not be parsed.",
+                journal.display()
+            )
+        })?;
+        tx.journal = Some(journal.to_owned());
+
+        if tx.committed {
+            tx.commit()
+        } else {
+            tx.rollback(git)
+        }
+    }
+
+    /// Copy a set of source files to their destinations.
+    /// All files are staged first so nothing is overwritten if some copy fails.
+    pub fn write_files(&mut self, files: &[(PathBuf, PathBuf)]) -> Result {
+        self.place_files(files, |src, tmp| fs::copy(src, tmp).map(|_| ()))
+    }
+
+    /// Create symbolic links at the destinations pointing to a set of source files.
+    /// All links are staged first so nothing is overwritten if some link fails.
+    pub fn link_files(&mut self, files: &[(PathBuf, PathBuf)]) -> Result {
+        self.place_files(files, |src, tmp| std::os::unix::fs::symlink(src, tmp))
+    }
+
+    /// Stage every source file next to its destination via a given function
+    /// and then move the staged files into place.
+    fn place_files<F>(&mut self, files: &[(PathBuf, PathBuf)], stage: F) -> Result
+    where
+        F: Fn(&Path, &Path) -> std::io::Result<()>,
+    {
+        let mut staged = vec![];
+        let result = files.iter().try_for_each(|(src, dest)| {
+            let tmp = with_suffix(dest, STAGED_SUFFIX);
+            self.record(Change::Staged { path: tmp.clone() })?;
+            stage(src, &tmp).with_context(|| {
+                format!(
+                    "file \"{}\" could not be placed at \"{}\".",
+                    src.display(),
+                    dest.display()
+                )
+            })?;
+            staged.push((tmp, dest));
+            Ok(())
+        });
+        let result = result.and_then(|_| {
+            staged.iter().try_for_each(|(tmp, dest)| {
+                self.backup(dest)?;
+                fs::rename(tmp, dest)
+                    .with_context(|| format!("file \"{}\" could not be written.", dest.display()))
+            })
+        });
+
+        // Drop the staged files left behind by a failure
+        if result.is_err() {
+            for (tmp, _) in &staged {
+                fs::remove_file(tmp).ok();
+            }
+        }
+        result
+    }
+
+    /// Remove a file keeping its content until the transaction is committed.
+    pub fn remove_file(&mut self, path: &Path) -> Result {
+        if !exists(path) {
+            return Ok(());
+        }
+        self.backup(path)?;
+
+        // A file already written by the transaction keeps its original content backed up
+        if exists(path) {
+            fs::remove_file(path)
+                .with_context(|| format!("file \"{}\" could not be removed.", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Check if the transaction has not recorded any change yet.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Keep the current content of a file which is going to be rewritten
+    /// until the transaction is committed.
+    pub fn keep_file(&mut self, path: &Path) -> Result {
+        self.backup(path)
+    }
+
+    /// Record a package repository cloned into a new directory.
+    pub fn cloned(&mut self, repo_dir: &Path) -> Result {
+        self.record(Change::Clone {
+            repo_dir: repo_dir.to_owned(),
+        })
+    }
+
+    /// Remove a package repository keeping it until the transaction is committed.
+    pub fn remove_repo(&mut self, repo_dir: &Path) -> Result {
+        let backup = with_suffix(repo_dir, BACKUP_SUFFIX);
+        if backup.exists() {
+            fs::remove_dir_all(&backup)?;
+        }
+        self.record(Change::Removal {
+            repo_dir: repo_dir.to_owned(),
+            backup: backup.clone(),
+        })?;
+        fs::rename(repo_dir, backup)
+            .with_context(|| format!("directory \"{}\" could not be removed.", repo_dir.display()))
+    }
+
+    /// Record a package repository checked out from a given commit.
+    pub fn checked_out(&mut self, user_repo_name: &str, commit: &str) -> Result {
+        self.record(Change::Checkout {
+            user_repo_name: user_repo_name.to_owned(),
+            commit: commit.to_owned(),
+        })
+    }
+
+    /// Add a change to the transaction writing it to the journal file (if any).
+    fn record(&mut self, change: Change) -> Result {
+        self.changes.push(change);
+        self.save()
+    }
+
+    /// Write the transaction to its journal file (if any).
+    fn save(&self) -> Result {
+        let Some(journal) = &self.journal else {
+            return Ok(());
+        };
+        let data = toml::to_string(self)
+            .with_context(|| "transaction journal could not be serialized.")?;
+        file::write_atomic(journal, &data).with_context(|| {
+            format!(
+                "transaction journal file at \"{}\" could not be written.",
+                journal.display()
+            )
+        })
+    }
+
+    /// Remove the journal file (if any) once the transaction is finished.
+    fn finish(&self) -> Result {
+        match &self.journal {
+            Some(journal) => remove_if_exists(journal).with_context(|| {
+                format!(
+                    "transaction journal file at \"{}\" could not be removed.",
+                    journal.display()
+                )
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Move an existing file to its backup path before it is overwritten or removed.
+    /// Only the first change of a file is backed up so its original content is kept.
+    fn backup(&mut self, path: &Path) -> Result {
+        let is_tracked = self
+            .changes
+            .iter()
+            .any(|c| matches!(c, Change::File { path: p, .. } if p == path));
+        if is_tracked {
+            return Ok(());
+        }
+
+        // The change is recorded before the file is moved so it's never lost
+        let backup = exists(path).then(|| with_suffix(path, BACKUP_SUFFIX));
+        self.record(Change::File {
+            path: path.to_owned(),
+            backup: backup.clone(),
+        })?;
+        if let Some(backup) = backup {
+            fs::rename(path, backup)
+                .with_context(|| format!("file \"{}\" could not be backed up.", path.display()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Make all changes permanent removing the backup files.
+    pub fn commit(mut self) -> Result {
+        self.committed = true;
+        self.save()?;
+        for change in &self.changes {
+            match change {
+                Change::File {
+                    backup: Some(backup),
+                    ..
+                } => remove_if_exists(backup).with_context(|| {
+                    format!("backup file \"{}\" could not be removed.", backup.display())
+                })?,
+                Change::Removal { backup, .. } if backup.exists() => fs::remove_dir_all(backup)
+                    .with_context(|| {
+                        format!(
+                            "backup directory \"{}\" could not be removed.",
+                            backup.display()
+                        )
+                    })?,
+                _ => {}
+            }
+        }
+        self.finish()
+    }
+
+    /// Revert all changes in reverse order restoring the previous state.
+    /// Every change is reverted even if some of them fail returning the first error.
+    /// Changes which were recorded but not applied yet (e.g interrupted ones) are skipped.
+    pub fn rollback(self, git: &Git) -> Result {
+        let mut result = Ok(());
+        for change in self.changes.iter().rev() {
+            let reverted = match change {
+                Change::File {
+                    path,
+                    backup: Some(backup),
+                } if exists(backup) => fs::rename(backup, path)
+                    .with_context(|| format!("file \"{}\" could not be restored.", path.display())),
+                Change::File {
+                    backup: Some(_), ..
+                } => Ok(()),
+                Change::File { path, backup: None } | Change::Staged { path } => {
+                    remove_if_exists(path).with_context(|| {
+                        format!("file \"{}\" could not be removed.", path.display())
+                    })
+                }
+                Change::Clone { repo_dir } if repo_dir.exists() => fs::remove_dir_all(repo_dir)
+                    .with_context(|| {
+                        format!("directory \"{}\" could not be removed.", repo_dir.display())
+                    }),
+                Change::Clone { .. } => Ok(()),
+                Change::Removal { repo_dir, backup } if backup.exists() => {
+                    fs::rename(backup, repo_dir).with_context(|| {
+                        format!(
+                            "directory \"{}\" could not be restored.",
+                            repo_dir.display()
+                        )
+                    })
+                }
+                Change::Removal { .. } => Ok(()),
+                Change::Checkout {
+                    user_repo_name,
+                    commit,
+                } => git
+                    .checkout(user_repo_name, Some(commit))
+                    .map(|_| ())
+                    .with_context(|| {
+                        format!(
+                            "package `{}` could not be restored to commit `{}`.",
+                            user_repo_name, commit
+                        )
+                    }),
+            };
+            if result.is_ok() {
+                result = reverted;
+            }
+        }
+        self.finish().and(result)
+    }
+}
+
+/// Check if a file exists without following symbolic links so broken links are found too.
+fn exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
+
+/// Remove a file ignoring it if it doesn't exist.
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Return a path with a suffix appended to its file name. E.g `foo.fish.paket-old`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
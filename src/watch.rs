@@ -0,0 +1,185 @@
+#[cfg(target_os = "linux")]
+pub use self::inotify::Watcher;
+#[cfg(not(target_os = "linux"))]
+pub use self::unsupported::Watcher;
+
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use crate::result::Result;
+
+    /// Watcher of the changes of a directory tree which is only supported on Linux for now.
+    pub struct Watcher;
+
+    impl Watcher {
+        /// Start watching a directory tree.
+        pub fn new(_root: &Path) -> Result<Self> {
+            bail!("watching directories for changes is only supported on Linux.")
+        }
+
+        /// Block until some change happens.
+        pub fn wait(&mut self, _debounce: Duration) -> Result {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::collections::BTreeMap;
+    use std::ffi::{CString, OsStr};
+    use std::fs;
+    use std::io;
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use crate::result::{Context, Result};
+
+    /// Events reported for every watched directory.
+    const WATCH_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MODIFY
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE_SELF;
+
+    /// Watcher of the changes of a directory tree based on Linux `inotify`.
+    /// Git directories are not watched.
+    pub struct Watcher {
+        fd: libc::c_int,
+        root_wd: libc::c_int,
+        dirs: BTreeMap<libc::c_int, PathBuf>,
+    }
+
+    impl Watcher {
+        /// Start watching a directory tree including its subdirectories created later.
+        pub fn new(root: &Path) -> Result<Self> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error())
+                    .with_context(|| "file watcher could not be initialized.");
+            }
+
+            let mut watcher = Self {
+                fd,
+                root_wd: -1,
+                dirs: BTreeMap::new(),
+            };
+            watcher.root_wd = watcher.add_tree(root)?;
+            Ok(watcher)
+        }
+
+        /// Block until some change happens and no other one follows within a debounce delay
+        /// so bursts of writes (e.g editors saving files) are handled at once.
+        pub fn wait(&mut self, debounce: Duration) -> Result {
+            self.read_events(None)?;
+            while self.read_events(Some(debounce))? {}
+            Ok(())
+        }
+
+        /// Watch a directory along with its subdirectories returning its watch descriptor.
+        fn add_tree(&mut self, dir: &Path) -> Result<libc::c_int> {
+            let dir_c = CString::new(dir.as_os_str().as_bytes())?;
+            let wd = unsafe { libc::inotify_add_watch(self.fd, dir_c.as_ptr(), WATCH_MASK) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error()).with_context(|| {
+                    format!("directory \"{}\" could not be watched.", dir.display())
+                });
+            }
+            self.dirs.insert(wd, dir.to_owned());
+
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() && entry.file_name() != ".git" {
+                    self.add_tree(&entry.path())?;
+                }
+            }
+
+            Ok(wd)
+        }
+
+        /// Read the pending events waiting up to a given timeout (or forever)
+        /// returning `false` if no event arrived.
+        fn read_events(&mut self, timeout: Option<Duration>) -> Result<bool> {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout = timeout.map_or(-1, |t| t.as_millis() as libc::c_int);
+            let ready = unsafe { libc::poll(&mut pollfd, 1, timeout) };
+            if ready < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    return Ok(false);
+                }
+                return Err(err).with_context(|| "file changes could not be watched.");
+            }
+            if ready == 0 {
+                return Ok(false);
+            }
+
+            let mut buf = [0u8; 4096];
+            let len = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), buf.len()) };
+            if len < 0 {
+                return Err(io::Error::last_os_error())
+                    .with_context(|| "file changes could not be read.");
+            }
+
+            // Every event is followed by the null-padded name of the changed entry (if any)
+            let len = len as usize;
+            let mut offset = 0;
+            while offset + mem::size_of::<libc::inotify_event>() <= len {
+                let event = unsafe {
+                    std::ptr::read_unaligned(buf.as_ptr().add(offset).cast::<libc::inotify_event>())
+                };
+                let name_start = offset + mem::size_of::<libc::inotify_event>();
+                let name_end = (name_start + event.len as usize).min(len);
+                let name = &buf[name_start..name_end];
+                let name = name.split(|b| *b == 0).next().unwrap_or_default();
+                self.handle_event(&event, OsStr::from_bytes(name))?;
+                offset = name_end;
+            }
+
+            Ok(true)
+        }
+
+        /// Keep the watched directories up to date with the changes of the tree.
+        fn handle_event(&mut self, event: &libc::inotify_event, name: &OsStr) -> Result {
+            if event.wd == self.root_wd
+                && event.mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF) != 0
+            {
+                let root = self.dirs.get(&event.wd).cloned().unwrap_or_default();
+                bail!("directory \"{}\" was removed or moved.", root.display());
+            }
+            if event.mask & libc::IN_IGNORED != 0 {
+                self.dirs.remove(&event.wd);
+                return Ok(());
+            }
+
+            // Watch the new subdirectories which could disappear right away
+            let is_new_dir = event.mask & libc::IN_ISDIR != 0
+                && event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0;
+            if is_new_dir && name != ".git" {
+                if let Some(dir) = self.dirs.get(&event.wd) {
+                    let dir = dir.join(name);
+                    self.add_tree(&dir).ok();
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}